@@ -0,0 +1,176 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use crate::error::Rdf2HdtError;
+use crate::integrity::{Bitmap, TriplesSections, read_triples_sections};
+use std::path::Path;
+
+/// The bitmap triples section of an HDT file, decoded without the hdt crate.
+///
+/// Meant to check what the hdt crate wrote: the IDs can be compared with those
+/// the hdt crate reads back, or mapped to terms with
+/// [`term_for_id`](crate::dictionary::term_for_id).
+pub struct BitmapTriples {
+    sections: TriplesSections,
+}
+
+impl BitmapTriples {
+    /// Reads the triples section of the HDT file at `path`, checking its
+    /// checksums. The dictionary is skipped.
+    ///
+    /// Only the SPO order written by the hdt crate is supported.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Rdf2HdtError> {
+        let sections = read_triples_sections(path.as_ref())?;
+        let triples = Self { sections };
+        triples.check_lengths()?;
+        Ok(triples)
+    }
+
+    /// Number of triples.
+    pub fn len(&self) -> u64 {
+        self.sections.seq_z.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `(subject, predicate, object)` IDs of the triples, in SPO order.
+    ///
+    /// Subjects are implicit, numbered from 1 in order. For every subject, the Y
+    /// sequence lists its predicates, the last of which is marked in the Y
+    /// bitmap, and for every subject and predicate, the Z sequence lists the
+    /// objects, the last of which is marked in the Z bitmap.
+    pub fn triple_ids(&self) -> impl Iterator<Item = (u64, u64, u64)> + '_ {
+        let TriplesSections {
+            bitmap_y,
+            bitmap_z,
+            seq_y,
+            seq_z,
+        } = &self.sections;
+        let (mut subject, mut y) = (1, 0);
+        (0..seq_z.len).map(move |z| {
+            let triple = (subject, seq_y.get(y), seq_z.get(z));
+            if bitmap_z.get(z) {
+                if bitmap_y.get(y) {
+                    subject += 1;
+                }
+                y += 1;
+            }
+            triple
+        })
+    }
+
+    /// Checks that the bitmaps and sequences have matching lengths, so
+    /// [`BitmapTriples::triple_ids`] can't read past their ends.
+    fn check_lengths(&self) -> Result<(), Rdf2HdtError> {
+        let TriplesSections {
+            bitmap_y,
+            bitmap_z,
+            seq_y,
+            seq_z,
+        } = &self.sections;
+        let pairs = ones(bitmap_z);
+        if bitmap_y.len != seq_y.len || bitmap_z.len != seq_z.len || pairs != seq_y.len {
+            return Err(Rdf2HdtError::InvalidInput(format!(
+                "inconsistent triples section: {} predicate entries and {} bits, {} object \
+                 entries and {} bits ending {pairs} lists",
+                seq_y.len, bitmap_y.len, seq_z.len, bitmap_z.len
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Number of bits set in `bitmap`.
+fn ones(bitmap: &Bitmap) -> u64 {
+    (0..bitmap.len).filter(|i| bitmap.get(*i)).count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{Options, build_hdt};
+    use crate::dictionary::{TermRole, id_for_term};
+    use crate::integrity::LogSequence;
+
+    /// Packs `values` in `bits` bits each, the way HDT log sequences store them.
+    fn sequence(bits: u8, values: &[u64]) -> LogSequence {
+        let mut data = vec![0; (values.len() * bits as usize).div_ceil(8)];
+        for (i, value) in values.iter().enumerate() {
+            for bit in 0..bits as usize {
+                let position = i * bits as usize + bit;
+                data[position / 8] |= ((value >> bit & 1) as u8) << (position % 8);
+            }
+        }
+        LogSequence {
+            bits,
+            len: values.len() as u64,
+            data,
+        }
+    }
+
+    fn bitmap(bits: &[bool]) -> Bitmap {
+        let mut data = vec![0; bits.len().div_ceil(8)];
+        for (i, bit) in bits.iter().enumerate() {
+            data[i / 8] |= u8::from(*bit) << (i % 8);
+        }
+        Bitmap {
+            len: bits.len() as u64,
+            data,
+        }
+    }
+
+    #[test]
+    fn decode_ids() -> Result<(), Rdf2HdtError> {
+        let expected = [
+            (1, 1, 2),
+            (1, 1, 3),
+            (1, 2, 1),
+            (2, 1, 9),
+            (3, 2, 4),
+            (3, 3, 4),
+            (3, 3, 5),
+        ];
+        // the encoding of `expected`: one entry per (subject, predicate) pair
+        // in Y, one per triple in Z, with the last of each list marked
+        let (t, f) = (true, false);
+        let triples = BitmapTriples {
+            sections: TriplesSections {
+                bitmap_y: bitmap(&[f, t, t, f, t]),
+                bitmap_z: bitmap(&[f, t, t, t, t, f, t]),
+                seq_y: sequence(2, &[1, 2, 1, 2, 3]),
+                seq_z: sequence(4, &[2, 3, 1, 9, 4, 4, 5]),
+            },
+        };
+        triples.check_lengths()?;
+        assert_eq!(triples.len(), 7);
+        assert_eq!(triples.triple_ids().collect::<Vec<_>>(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_built_hdt() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        let expected = hdt
+            .triples_with_pattern(None, None, None)
+            .map(|[s, p, o]| {
+                let id = |term: &str, role| id_for_term(&hdt, term, role).unwrap() as u64;
+                (
+                    id(&s, TermRole::Subject),
+                    id(&p, TermRole::Predicate),
+                    id(&o, TermRole::Object),
+                )
+            })
+            .collect::<Vec<_>>();
+        let triples = BitmapTriples::read(tmp_hdt.path())?;
+        assert_eq!(triples.len(), 9);
+        assert_eq!(triples.triple_ids().collect::<Vec<_>>(), expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use oxrdf::{BlankNode, NamedOrBlankNode, Term, Triple};
+use std::collections::HashMap;
+
+/// Assigns the blank node labels stored in the HDT when
+/// [`Options::canonical_bnodes`](crate::builder::Options::canonical_bnodes) is
+/// set.
+///
+/// Triples are passed in input order, after the labels of each file have been
+/// scoped to it, see
+/// [`Options::shared_bnode_scope`](crate::builder::Options::shared_bnode_scope).
+/// The built-in implementation is [`CounterCanonicalizer`], another can be set
+/// with [`Options::bnode_canonicalizer`](crate::builder::Options::bnode_canonicalizer).
+pub trait BnodeCanonicalizer {
+    /// Returns the new label of `node`.
+    ///
+    /// The same input label must always get the same new label, and different
+    /// input labels different ones, so node identity is preserved across the
+    /// whole dataset.
+    fn relabel(&mut self, node: &BlankNode) -> BlankNode;
+
+    /// Relabels the blank nodes of `triple`, including those of quoted triples.
+    fn canonicalize(&mut self, triple: &mut Triple) {
+        if let NamedOrBlankNode::BlankNode(b) = &mut triple.subject {
+            *b = self.relabel(b);
+        }
+        match &mut triple.object {
+            Term::BlankNode(b) => *b = self.relabel(b),
+            Term::Triple(quoted) => self.canonicalize(quoted),
+            _ => {}
+        }
+    }
+}
+
+/// Relabels blank nodes `b0`, `b1`, ... in order of first appearance, for a
+/// prefix `b`.
+#[derive(Debug, Default)]
+pub struct CounterCanonicalizer {
+    prefix: String,
+    labels: HashMap<String, BlankNode>,
+}
+
+impl CounterCanonicalizer {
+    /// Numbers labels after `prefix`, which must be a valid blank node label.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            labels: HashMap::new(),
+        }
+    }
+}
+
+impl BnodeCanonicalizer for CounterCanonicalizer {
+    fn relabel(&mut self, node: &BlankNode) -> BlankNode {
+        if let Some(label) = self.labels.get(node.as_str()) {
+            return label.clone();
+        }
+        let label = BlankNode::new_unchecked(format!("{}{}", self.prefix, self.labels.len()));
+        self.labels.insert(node.as_str().to_string(), label.clone());
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxrdf::NamedNode;
+
+    #[test]
+    fn counter_labels() {
+        let p = NamedNode::new_unchecked("http://example.org/p");
+        let x = BlankNode::new_unchecked("x");
+        let y = BlankNode::new_unchecked("y");
+        let quoted = Triple::new(y.clone(), p.clone(), x.clone());
+        let mut triple = Triple::new(x.clone(), p.clone(), quoted);
+        let mut canonicalizer = CounterCanonicalizer::new("c");
+        canonicalizer.canonicalize(&mut triple);
+
+        let c0 = BlankNode::new_unchecked("c0");
+        let c1 = BlankNode::new_unchecked("c1");
+        let expected = Triple::new(c0.clone(), p.clone(), Triple::new(c1, p, c0.clone()));
+        assert_eq!(triple, expected);
+        assert_eq!(canonicalizer.relabel(&x), c0);
+    }
+}
@@ -1,55 +1,1608 @@
 // Copyright (c) 2025, Decisym, LLC
 // Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
 
-use crate::rdf_reader::convert_to_nt;
+use crate::bnodes::BnodeCanonicalizer;
+use crate::dictionary::{DictionaryTerms, TermRole, id_for_term, term_for_id, term_sections};
+use crate::error::Rdf2HdtError;
+use crate::header::{HeaderProperties, HeaderWriter};
+use crate::integrity::{Sha256Writer, write_sha256_file};
+use crate::manifest::write_manifest;
+use crate::rdf_reader::{
+    Chain, FileReader, HdtReader, RdfReader, ReadStats, STDIN_PATH, Shard, hdt_string,
+    locate_nt_error, scan_nt_file, write_files_nt, write_nt,
+};
 use log::{debug, error};
+use oxrdf::Triple;
+pub use oxrdfio::RdfFormat;
 use std::{
-    fs::OpenOptions,
-    io::{BufWriter, Write},
+    collections::{HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
-pub fn build_hdt(file_paths: Vec<String>, dest_file: &str) -> Result<hdt::Hdt, hdt::hdt::Error> {
+/// Options controlling the RDF to HDT conversion.
+///
+/// # N-Triples input
+///
+/// A single `.nt` input is normally handed to the hdt crate as is, as the
+/// quickest way to build the HDT. rdf2hdt first parses it once to check its
+/// triples and count them, so the file is parsed twice in all, but no
+/// intermediate file is written. These options need every triple to go through
+/// rdf2hdt's reader, so with any of them set that input is parsed and written to
+/// an intermediate file like the other formats, which takes about as much disk
+/// space as the input:
+///
+/// - [`Options::lenient`]
+/// - [`Options::canonical_bnodes`]
+/// - [`Options::normalize_lang_tags`] and [`Options::canonicalize_literals`]
+/// - [`Options::rdf_star`] set to [`RdfStarMode::Reify`]
+/// - [`Options::max_triples`] and [`Options::max_distinct_terms`]
+/// - [`Options::normalize_iris`] and [`Options::canonical_trailing_slash`]
+/// - [`Options::input_format`] or [`Options::file_formats`] giving a format
+///   other than N-Triples
+/// - [`Options::exclude_predicates`], [`Options::include_only_predicates`] and
+///   [`Options::triple_filter`]
+///
+/// A file that starts with a byte order mark, has CRLF line breaks or writes out
+/// the `xsd:string` datatype of a literal, which the hdt crate doesn't read the
+/// way rdf2hdt does, goes through the reader as well, after the check.
+#[derive(Default)]
+pub struct Options {
+    /// Callback invoked with the current phase and the number of items processed in it.
+    ///
+    /// While parsing, it is called every [`PROGRESS_INTERVAL`] triples, from the
+    /// parser threads when several files are parsed in parallel. Sorting and
+    /// dictionary/triples construction run inside the hdt crate, so those phases
+    /// are only reported once they have completed.
+    pub progress: Option<Box<dyn Fn(BuildPhase, u64) + Send + Sync>>,
+    /// Directory for intermediate files, such as the temporary N-Triples file.
+    ///
+    /// Defaults to the system temp directory, which may be a small tmpfs on some systems.
+    pub temp_dir: Option<PathBuf>,
+    /// Keep intermediate files instead of deleting them once they have been consumed.
+    ///
+    /// Mostly useful to inspect the N-Triples file handed to the hdt crate.
+    pub keep_intermediate: bool,
+    /// Log and skip invalid statements instead of failing the conversion.
+    ///
+    /// Input is checked by the RDF parsers: malformed IRIs, language tags and
+    /// literals are syntax errors. Invalid UTF-8 is replaced with U+FFFD and
+    /// logged with its byte offset.
+    pub lenient: bool,
+    /// Relabel blank nodes deterministically, in order of first appearance.
+    ///
+    /// Blank node labels otherwise come from the input, and parsers assign random
+    /// labels to anonymous nodes such as `[]`. Turn this on for reproducible output,
+    /// in particular when merging several files that should always produce the
+    /// same dictionary. Input files are read in sorted order, so the labels don't
+    /// depend on the order the files are given in. For byte-identical files, also
+    /// fix the creation time in the header, see [`Options::header_metadata`]. See
+    /// [`Options::bnode_canonicalizer`] to assign labels some other way.
+    pub canonical_bnodes: bool,
+    /// Treat blank node labels as shared by all the input files, so `_:b0` in two
+    /// files is the same node.
+    ///
+    /// By default, as RDF defines, labels are scoped to their file: when several
+    /// files are read, each label is prefixed with the position of its file in the
+    /// sorted input, e.g. `_:f1_b0`. JSON-LD blank nodes are always scoped to their
+    /// document.
+    pub shared_bnode_scope: bool,
+    /// Apply the BCP 47 casing conventions to language tags, e.g. `en-US` and
+    /// `zh-Hant-TW`.
+    ///
+    /// Language tags are case-insensitive, and the parsers already lowercase
+    /// them, so `"foo"@EN` and `"foo"@en` are the same term either way. This only
+    /// changes the casing of the stored tags, never the literal values.
+    pub normalize_lang_tags: bool,
+    /// Rewrite `xsd:integer`, `xsd:decimal`, `xsd:boolean` and `xsd:double`
+    /// literals in their canonical lexical form, so that `"01"^^xsd:integer` and
+    /// `"1"^^xsd:integer` are a single term.
+    ///
+    /// This is lossy: the original lexical forms are not kept, and doubles are
+    /// rounded to 64-bit precision. Literals that aren't valid for their datatype,
+    /// and other datatypes, are left untouched.
+    pub canonicalize_literals: bool,
+    /// How to handle RDF-star quoted triples, which HDT cannot encode.
+    pub rdf_star: RdfStarMode,
+    /// How to handle generalized RDF triples in N3 input, such as triples with a
+    /// literal subject, which HDT cannot encode.
+    ///
+    /// Other formats cannot express them, the parsers report them as syntax errors.
+    pub generalized_rdf: GeneralizedRdfMode,
+    /// Fetch remote `@context` documents referenced by JSON-LD input over HTTP.
+    ///
+    /// Off by default, so converting a document never makes network requests. A
+    /// JSON-LD document with a remote context then fails to parse. Needs the
+    /// `remote-contexts` cargo feature, without it reading JSON-LD with this set
+    /// fails with [`Rdf2HdtError::InvalidInput`].
+    pub resolve_remote_contexts: bool,
+    /// Read the HDT back once it has been written to a file and compare its
+    /// triples with the ones built, failing with [`Rdf2HdtError::Verification`]
+    /// at the first difference.
+    ///
+    /// Off by default since it doubles the I/O. Output written with
+    /// [`build_hdt_to_writer`] is not verified.
+    pub verify: bool,
+    /// Maximum number of threads parsing input files in parallel.
+    ///
+    /// Defaults to one thread per CPU. `Some(1)` parses files one after the other.
+    pub parser_threads: Option<usize>,
+    /// Base IRI that relative IRIs are resolved against, unless the document
+    /// declares its own base, e.g. with `@base` in Turtle.
+    ///
+    /// Defaults to `file://` followed by the input file name, which is rarely a
+    /// meaningful IRI. Stdin has no default base, so relative IRIs read from it
+    /// are syntax errors, and skipped under [`Options::lenient`].
+    pub base_iri: Option<String>,
+    /// Extra properties of the dataset in the HDT header, as property IRI and
+    /// literal value.
+    ///
+    /// They replace any value the header already has for the property. The
+    /// header also gets the usual statistics, the creation time and the rdf2hdt
+    /// version, unless the hdt crate already wrote them. The creation time is
+    /// the current time, so two builds of the same input differ, unless the
+    /// `SOURCE_DATE_EPOCH` environment variable gives it in seconds since the
+    /// Unix epoch, or it is set here as `http://purl.org/dc/terms/issued`.
+    pub header_metadata: HashMap<String, String>,
+    /// Stop reading the input after this many triples, and build the HDT from
+    /// those only.
+    ///
+    /// Meant for sampling and debugging: which triples are kept depends on the
+    /// input order and on how the parsers expand statements, and may change
+    /// between releases. Files are read one after the other in sorted order.
+    pub max_triples: Option<u64>,
+    /// Split the output of [`build_hdt_shards`] into files of about this many
+    /// triples each.
+    ///
+    /// Ignored by the other build functions, which always write a single HDT.
+    pub max_output_triples_per_file: Option<u64>,
+    /// Build an HDT without triples when the input has none, such as an empty
+    /// file or one with only comments.
+    ///
+    /// Otherwise the conversion fails with [`Rdf2HdtError::EmptyInput`]. This
+    /// includes inputs whose statements were all skipped under
+    /// [`Options::lenient`].
+    pub allow_empty: bool,
+    /// Drop the triples with one of these predicates, given as IRIs without angle
+    /// brackets.
+    ///
+    /// Triples are filtered once parsed, after RDF-star reification, so terms
+    /// that only appear in dropped triples aren't in the dictionary.
+    pub exclude_predicates: Vec<String>,
+    /// Keep only the triples with one of these predicates, given as IRIs without
+    /// angle brackets, see [`Options::exclude_predicates`].
+    pub include_only_predicates: Option<Vec<String>>,
+    /// Also write the input order of the triples next to the HDT, replacing the
+    /// `.hdt` extension with `.order`.
+    ///
+    /// HDT stores triples sorted by subject, predicate and object. Line `i` of the
+    /// order file, counting from 0, holds the position in the parsed input of the
+    /// `i`th triple of the HDT, that of its first occurrence for duplicates.
+    /// Positions count triples once parsed and filtered, with the files in sorted
+    /// order. Only [`build_hdt`] and [`build_hdt_with_stats`] write it.
+    pub emit_order_index: bool,
+    /// Dictionary encoding of the HDT.
+    ///
+    /// Only [`DictFormat::FourSectionPlain`] is written today, the hdt crate
+    /// has no other, and selecting another variant fails the conversion with
+    /// [`Rdf2HdtError::UnsupportedFormat`].
+    pub dictionary_format: DictFormat,
+    /// Abort the conversion with [`Rdf2HdtError::Cancelled`] once this is set.
+    ///
+    /// The flag is checked for every parsed triple and between the build phases,
+    /// but not while the hdt crate sorts the triples and builds the dictionary
+    /// and triples, which only stops once that phase completes. Intermediate
+    /// files are deleted as on any other error, and the destination file is left
+    /// untouched unless it was already being written.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Size of the buffer the HDT is written through, in bytes.
+    ///
+    /// Defaults to [`DEFAULT_WRITE_BUFFER_SIZE`]. Larger buffers mean fewer, larger
+    /// writes, which helps on network file systems.
+    pub write_buffer_size: Option<usize>,
+    /// Abort with [`Rdf2HdtError::LimitExceeded`] as soon as the parsed triples
+    /// have more than this many distinct terms, before the hdt crate builds the
+    /// dictionary.
+    ///
+    /// Terms are counted by their dictionary string, once however many positions
+    /// they appear in, after the predicate filters and the other options that
+    /// change triples are applied. The terms seen are kept in memory while
+    /// parsing, and files are read one after the other.
+    pub max_distinct_terms: Option<u64>,
+    /// Normalize the percent-encoding of IRIs, so that `%7e`, `%7E` and `~` are
+    /// the same term, as are `%2f` and `%2F`.
+    ///
+    /// Only the normalizations that keep the IRI's meaning are applied:
+    /// percent-encoded unreserved characters (letters, digits, `-`, `.`, `_` and
+    /// `~`) are decoded and other percent-encodings are uppercase. Datatype IRIs
+    /// are left untouched.
+    pub normalize_iris: bool,
+    /// Write a JSON description of the build to this file once the HDT has been
+    /// written.
+    ///
+    /// It lists the input files with their size and CRC32C, the output file and
+    /// its size, the [`BuildStats`], the options that affect the output, the
+    /// rdf2hdt version and the time of the build. Only written by [`build_hdt`],
+    /// [`build_hdt_with_stats`] and [`build_hdt_glob`].
+    pub emit_manifest: Option<PathBuf>,
+    /// Creates the [`BnodeCanonicalizer`] used by [`Options::canonical_bnodes`],
+    /// instead of a [`CounterCanonicalizer`](crate::bnodes::CounterCanonicalizer)
+    /// numbering nodes `b0`, `b1`, ...
+    ///
+    /// It is called once per conversion, so the labels of a conversion don't
+    /// depend on earlier ones. Ignored unless [`Options::canonical_bnodes`] is set.
+    pub bnode_canonicalizer: Option<Box<dyn Fn() -> Box<dyn BnodeCanonicalizer> + Send + Sync>>,
+    /// `(prefix, namespace)` pairs used, with the
+    /// [`COMMON_PREFIXES`](crate::vocab::COMMON_PREFIXES), to shorten the IRIs of
+    /// the statements shown in log messages, e.g. `ex:alice foaf:knows ex:bob`.
+    pub log_prefixes: Vec<(String, String)>,
+    /// Write the SHA-256 of each HDT file to the file name with a `.sha256`
+    /// suffix, e.g. `data.hdt.sha256`, in the format `sha256sum -c` checks.
+    ///
+    /// The digest is computed while the HDT is written, without reading it back.
+    /// To checksum the output of [`build_hdt_to_writer`], wrap its writer in a
+    /// [`Sha256Writer`].
+    pub emit_checksum: bool,
+    /// Format of every input file, instead of the one given by its extension.
+    ///
+    /// Meant for input without a meaningful file name, such as a request body
+    /// saved to a temporary file, whose `Content-Type` gives the format through
+    /// [`RdfFormat::from_media_type`]. Required to read stdin, selected by passing
+    /// `-` as the only input path, which has no extension. A `.gz` suffix or gzip
+    /// magic bytes are still decompressed.
+    pub input_format: Option<RdfFormat>,
+    /// Count the triples of every predicate into
+    /// [`BuildStats::predicate_histogram`].
+    ///
+    /// The counts come from one predicate-bound query on the built HDT per
+    /// predicate, which the triples index answers without decoding the other
+    /// triples.
+    pub emit_predicate_histogram: bool,
+    /// Remove the trailing slash of IRIs, so that `http://example.org/ns/` and
+    /// `http://example.org/ns` are the same term.
+    ///
+    /// This is lossy: the two forms are different IRIs, which may name different
+    /// resources, and relative IRIs resolved against the stored one no longer
+    /// give the same result. Only meant for vocabularies known to use both forms
+    /// for one resource. Applied after [`Options::normalize_iris`], datatype
+    /// IRIs are left untouched.
+    pub canonical_trailing_slash: bool,
+    /// Format of specific input files, by path as given in the input list.
+    ///
+    /// Takes precedence over [`Options::input_format`] and the extension for
+    /// those files only, the others are still detected from their extension.
+    /// Stdin is read with [`Options::input_format`]. A `.gz` suffix or gzip magic
+    /// bytes are still decompressed.
+    pub file_formats: HashMap<String, RdfFormat>,
+    /// Keep only the triples for which this returns `true`.
+    ///
+    /// Called on every triple once parsed, after RDF-star reification and the
+    /// predicate filters, from the parser threads when several files are parsed
+    /// in parallel. Like [`Options::exclude_predicates`], terms that only appear
+    /// in dropped triples aren't in the dictionary. The call happens for every
+    /// triple, so a slow filter slows down the whole parsing phase; prefer the
+    /// predicate lists where they are enough.
+    pub triple_filter: Option<TripleFilter>,
+}
+
+/// Predicate deciding which triples are kept, see [`Options::triple_filter`].
+pub type TripleFilter = Box<dyn Fn(&Triple) -> bool + Send + Sync>;
+
+/// Handling of RDF-star quoted triples, see [`Options::rdf_star`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RdfStarMode {
+    /// Fail the conversion on a quoted triple, or skip the statement under
+    /// [`Options::lenient`].
+    #[default]
+    Reject,
+    /// Replace each quoted triple with a blank node, described by the standard
+    /// `rdf:subject`, `rdf:predicate` and `rdf:object` reification triples.
+    ///
+    /// The blank node label is derived from the quoted triple, so the same triple
+    /// always gets the same node, across files and across conversions. Quoted
+    /// triples containing blank nodes, and the reifiers created for `<< >>`
+    /// annotations, also need [`Options::canonical_bnodes`] to be reproducible.
+    Reify,
+}
+
+/// Handling of generalized RDF triples, see [`Options::generalized_rdf`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GeneralizedRdfMode {
+    /// Fail the conversion on a generalized triple, or skip the statement under
+    /// [`Options::lenient`].
+    #[default]
+    Reject,
+    /// Log and skip generalized triples, counting them in [`BuildStats::skipped`].
+    Skip,
+}
+
+/// Dictionary encodings of an HDT, see [`Options::dictionary_format`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DictFormat {
+    /// Four sections of plain front coded strings, for shared terms, subjects,
+    /// predicates and objects: `<http://purl.org/HDT/hdt#dictionaryFour>`.
+    #[default]
+    FourSectionPlain,
+    /// Separate object sections per literal datatype and language:
+    /// `<http://purl.org/HDT/hdt#dictionaryMult>`, not supported yet.
+    MultipleSections,
+}
+
+impl Options {
+    /// Creates an intermediate file in [`Options::temp_dir`].
+    ///
+    /// The file is deleted when dropped, unless [`Options::keep_intermediate`] is set.
+    pub(crate) fn temp_file(&self, suffix: &str) -> std::io::Result<tempfile::NamedTempFile> {
+        let mut builder = tempfile::Builder::new();
+        builder
+            .disable_cleanup(self.keep_intermediate)
+            .suffix(suffix);
+        match &self.temp_dir {
+            Some(dir) => builder.tempfile_in(dir),
+            None => builder.tempfile(),
+        }
+    }
+
+    /// Whether triples must go through rdf2hdt's reader, even for N-Triples input
+    /// that the hdt crate could read directly, as listed in the `Options` docs.
+    pub(crate) fn transforms_input(&self) -> bool {
+        self.lenient
+            || self.canonical_bnodes
+            || self.normalize_lang_tags
+            || self.canonicalize_literals
+            || self.rdf_star == RdfStarMode::Reify
+            || self.max_triples.is_some()
+            || self.max_distinct_terms.is_some()
+            || self.normalize_iris
+            || self.canonical_trailing_slash
+            || self.input_format.is_some_and(|f| f != RdfFormat::NTriples)
+            || self
+                .file_formats
+                .values()
+                .any(|f| *f != RdfFormat::NTriples)
+            || !self.exclude_predicates.is_empty()
+            || self.include_only_predicates.is_some()
+            || self.triple_filter.is_some()
+    }
+
+    /// Fails with [`Rdf2HdtError::Cancelled`] if [`Options::cancel`] is set.
+    pub(crate) fn check_cancelled(&self) -> Result<(), Rdf2HdtError> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            debug!("conversion cancelled");
+            return Err(Rdf2HdtError::Cancelled);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn report_progress(&self, phase: BuildPhase, count: u64) {
+        if let Some(progress) = &self.progress {
+            progress(phase, count);
+        }
+    }
+}
+
+/// Builds [`Options`] with chained method calls, then runs the conversion.
+///
+/// ```no_run
+/// use rdf2hdt::builder::HdtBuilder;
+///
+/// let hdt = HdtBuilder::new()
+///     .temp_dir("/var/tmp")
+///     .canonical_bnodes(true)
+///     .progress(|phase, count| eprintln!("{phase:?}: {count}"))
+///     .build(vec!["data.ttl".to_string()], "data.hdt")?;
+/// # Ok::<(), rdf2hdt::error::Rdf2HdtError>(())
+/// ```
+#[derive(Default)]
+pub struct HdtBuilder {
+    opts: Options,
+}
+
+impl HdtBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets [`Options::progress`].
+    pub fn progress(mut self, progress: impl Fn(BuildPhase, u64) + Send + Sync + 'static) -> Self {
+        self.opts.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Sets [`Options::temp_dir`].
+    pub fn temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.opts.temp_dir = Some(temp_dir.into());
+        self
+    }
+
+    /// Sets [`Options::keep_intermediate`].
+    pub fn keep_intermediate(mut self, keep_intermediate: bool) -> Self {
+        self.opts.keep_intermediate = keep_intermediate;
+        self
+    }
+
+    /// Sets [`Options::lenient`].
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.opts.lenient = lenient;
+        self
+    }
+
+    /// Sets [`Options::canonical_bnodes`].
+    pub fn canonical_bnodes(mut self, canonical_bnodes: bool) -> Self {
+        self.opts.canonical_bnodes = canonical_bnodes;
+        self
+    }
+
+    /// Sets [`Options::shared_bnode_scope`].
+    pub fn shared_bnode_scope(mut self, shared_bnode_scope: bool) -> Self {
+        self.opts.shared_bnode_scope = shared_bnode_scope;
+        self
+    }
+
+    /// Sets [`Options::normalize_lang_tags`].
+    pub fn normalize_lang_tags(mut self, normalize_lang_tags: bool) -> Self {
+        self.opts.normalize_lang_tags = normalize_lang_tags;
+        self
+    }
+
+    /// Sets [`Options::canonicalize_literals`].
+    pub fn canonicalize_literals(mut self, canonicalize_literals: bool) -> Self {
+        self.opts.canonicalize_literals = canonicalize_literals;
+        self
+    }
+
+    /// Sets [`Options::rdf_star`].
+    pub fn rdf_star(mut self, rdf_star: RdfStarMode) -> Self {
+        self.opts.rdf_star = rdf_star;
+        self
+    }
+
+    /// Sets [`Options::generalized_rdf`].
+    pub fn generalized_rdf(mut self, generalized_rdf: GeneralizedRdfMode) -> Self {
+        self.opts.generalized_rdf = generalized_rdf;
+        self
+    }
+
+    /// Sets [`Options::resolve_remote_contexts`].
+    pub fn resolve_remote_contexts(mut self, resolve_remote_contexts: bool) -> Self {
+        self.opts.resolve_remote_contexts = resolve_remote_contexts;
+        self
+    }
+
+    /// Sets [`Options::verify`].
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.opts.verify = verify;
+        self
+    }
+
+    /// Sets [`Options::parser_threads`].
+    pub fn parser_threads(mut self, parser_threads: usize) -> Self {
+        self.opts.parser_threads = Some(parser_threads);
+        self
+    }
+
+    /// Sets [`Options::base_iri`].
+    pub fn base_iri(mut self, base_iri: impl Into<String>) -> Self {
+        self.opts.base_iri = Some(base_iri.into());
+        self
+    }
+
+    /// Adds a property to [`Options::header_metadata`].
+    pub fn header_metadata(
+        mut self,
+        property: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.opts
+            .header_metadata
+            .insert(property.into(), value.into());
+        self
+    }
+
+    /// Sets [`Options::max_triples`].
+    pub fn max_triples(mut self, max_triples: u64) -> Self {
+        self.opts.max_triples = Some(max_triples);
+        self
+    }
+
+    /// Sets [`Options::max_output_triples_per_file`].
+    pub fn max_output_triples_per_file(mut self, max_output_triples_per_file: u64) -> Self {
+        self.opts.max_output_triples_per_file = Some(max_output_triples_per_file);
+        self
+    }
+
+    /// Sets [`Options::allow_empty`].
+    pub fn allow_empty(mut self, allow_empty: bool) -> Self {
+        self.opts.allow_empty = allow_empty;
+        self
+    }
+
+    /// Sets [`Options::exclude_predicates`].
+    pub fn exclude_predicates(mut self, exclude_predicates: Vec<String>) -> Self {
+        self.opts.exclude_predicates = exclude_predicates;
+        self
+    }
+
+    /// Sets [`Options::include_only_predicates`].
+    pub fn include_only_predicates(mut self, include_only_predicates: Vec<String>) -> Self {
+        self.opts.include_only_predicates = Some(include_only_predicates);
+        self
+    }
+
+    /// Sets [`Options::emit_order_index`].
+    pub fn emit_order_index(mut self, emit_order_index: bool) -> Self {
+        self.opts.emit_order_index = emit_order_index;
+        self
+    }
+
+    /// Sets [`Options::dictionary_format`].
+    pub fn dictionary_format(mut self, dictionary_format: DictFormat) -> Self {
+        self.opts.dictionary_format = dictionary_format;
+        self
+    }
+
+    /// Sets [`Options::cancel`].
+    pub fn cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.opts.cancel = Some(cancel);
+        self
+    }
+
+    /// Sets [`Options::write_buffer_size`].
+    pub fn write_buffer_size(mut self, write_buffer_size: usize) -> Self {
+        self.opts.write_buffer_size = Some(write_buffer_size);
+        self
+    }
+
+    /// Sets [`Options::max_distinct_terms`].
+    pub fn max_distinct_terms(mut self, max_distinct_terms: u64) -> Self {
+        self.opts.max_distinct_terms = Some(max_distinct_terms);
+        self
+    }
+
+    /// Sets [`Options::normalize_iris`].
+    pub fn normalize_iris(mut self, normalize_iris: bool) -> Self {
+        self.opts.normalize_iris = normalize_iris;
+        self
+    }
+
+    /// Sets [`Options::emit_manifest`].
+    pub fn emit_manifest(mut self, emit_manifest: impl Into<PathBuf>) -> Self {
+        self.opts.emit_manifest = Some(emit_manifest.into());
+        self
+    }
+
+    /// Sets [`Options::bnode_canonicalizer`].
+    pub fn bnode_canonicalizer(
+        mut self,
+        bnode_canonicalizer: impl Fn() -> Box<dyn BnodeCanonicalizer> + Send + Sync + 'static,
+    ) -> Self {
+        self.opts.bnode_canonicalizer = Some(Box::new(bnode_canonicalizer));
+        self
+    }
+
+    /// Adds a prefix to [`Options::log_prefixes`].
+    pub fn log_prefix(mut self, prefix: impl Into<String>, namespace: impl Into<String>) -> Self {
+        self.opts
+            .log_prefixes
+            .push((prefix.into(), namespace.into()));
+        self
+    }
+
+    /// Sets [`Options::emit_checksum`].
+    pub fn emit_checksum(mut self, emit_checksum: bool) -> Self {
+        self.opts.emit_checksum = emit_checksum;
+        self
+    }
+
+    /// Sets [`Options::input_format`].
+    pub fn input_format(mut self, input_format: RdfFormat) -> Self {
+        self.opts.input_format = Some(input_format);
+        self
+    }
+
+    /// Sets [`Options::emit_predicate_histogram`].
+    pub fn emit_predicate_histogram(mut self, emit_predicate_histogram: bool) -> Self {
+        self.opts.emit_predicate_histogram = emit_predicate_histogram;
+        self
+    }
+
+    /// Sets [`Options::canonical_trailing_slash`].
+    pub fn canonical_trailing_slash(mut self, canonical_trailing_slash: bool) -> Self {
+        self.opts.canonical_trailing_slash = canonical_trailing_slash;
+        self
+    }
+
+    /// Adds the format of `path` to [`Options::file_formats`].
+    pub fn file_format(mut self, path: impl Into<String>, format: RdfFormat) -> Self {
+        self.opts.file_formats.insert(path.into(), format);
+        self
+    }
+
+    /// Sets [`Options::triple_filter`].
+    pub fn triple_filter(
+        mut self,
+        triple_filter: impl Fn(&Triple) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.opts.triple_filter = Some(Box::new(triple_filter));
+        self
+    }
+
+    /// The options set so far.
+    pub fn options(&self) -> &Options {
+        &self.opts
+    }
+
+    /// Converts the RDF input to HDT, see [`build_hdt`].
+    pub fn build(
+        &self,
+        file_paths: Vec<String>,
+        dest_file: &str,
+    ) -> Result<hdt::Hdt, Rdf2HdtError> {
+        build_hdt(file_paths, dest_file, &self.opts)
+    }
+
+    /// Same as [`HdtBuilder::build`], additionally returning [`BuildStats`].
+    pub fn build_with_stats(
+        &self,
+        file_paths: Vec<String>,
+        dest_file: &str,
+    ) -> Result<(hdt::Hdt, BuildStats), Rdf2HdtError> {
+        build_hdt_with_stats(file_paths, dest_file, &self.opts)
+    }
+}
+
+impl From<HdtBuilder> for Options {
+    fn from(builder: HdtBuilder) -> Self {
+        builder.opts
+    }
+}
+
+/// Default [`Options::write_buffer_size`], 1 MiB.
+pub const DEFAULT_WRITE_BUFFER_SIZE: usize = 1 << 20;
+
+/// Number of items processed between two progress callbacks.
+pub const PROGRESS_INTERVAL: u64 = 100_000;
+
+/// Phases of an HDT build, as reported to [`Options::progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildPhase {
+    /// Parsing input triples, the count is the number of triples read so far.
+    Parsing,
+    /// Sorting triples in SPO order, the count is the number of triples.
+    SortingTriples,
+    /// Building the dictionary, the count is the number of distinct terms.
+    BuildingDictionary,
+    /// Building the triples section, the count is the number of triples.
+    BuildingTriples,
+    /// Writing the HDT output, the count is the number of triples written.
+    Writing,
+}
+
+/// Counts collected while building an HDT, useful to verify a conversion.
+#[derive(Debug, Clone, Default)]
+pub struct BuildStats {
+    /// Number of triples read from the input files.
+    pub input_triples: u64,
+    /// Number of invalid statements skipped under [`Options::lenient`], and of
+    /// generalized triples skipped with [`GeneralizedRdfMode::Skip`].
+    pub skipped: u64,
+    /// Number of triples dropped by [`Options::exclude_predicates`].
+    pub excluded: u64,
+    /// Number of triples dropped by [`Options::include_only_predicates`].
+    pub not_included: u64,
+    /// Number of triples dropped by [`Options::triple_filter`].
+    pub filtered: u64,
+    /// Number of terms left out of the dictionary because they only appear in
+    /// triples dropped by the predicate filters or [`Options::triple_filter`].
+    ///
+    /// Not counted by [`dry_run`].
+    pub pruned_terms: u64,
+    /// Suspicious but valid input, such as a Turtle prefix redefined with a
+    /// different IRI, which often comes from concatenated files, or an RDF or XSD
+    /// datatype missing from [`KNOWN_DATATYPES`](crate::vocab::KNOWN_DATATYPES).
+    /// These are also logged as warnings.
+    pub warnings: Vec<String>,
+    /// Size of the input files on disk, in bytes, or 0 when reading stdin.
+    pub input_bytes: u64,
+    /// Number of duplicate input triples that were discarded.
+    ///
+    /// Duplicates are collapsed once terms are mapped to their dictionary IDs,
+    /// so blank nodes are compared by identity rather than by label.
+    pub duplicates: u64,
+    /// Number of triples in the resulting HDT.
+    pub triples: u64,
+    /// Number of distinct subjects, including shared terms.
+    pub subjects: u64,
+    /// Number of distinct predicates.
+    pub predicates: u64,
+    /// Number of distinct objects, including shared terms.
+    pub objects: u64,
+    /// Number of terms used both as subject and object.
+    pub shared: u64,
+    /// Total wall time of the build.
+    pub elapsed: std::time::Duration,
+    /// Every predicate IRI with its number of triples, in dictionary order.
+    ///
+    /// Empty unless [`Options::emit_predicate_histogram`] is set.
+    pub predicate_histogram: Vec<(String, u64)>,
+}
+
+pub fn build_hdt(
+    file_paths: Vec<String>,
+    dest_file: &str,
+    opts: &Options,
+) -> Result<hdt::Hdt, Rdf2HdtError> {
+    build_hdt_with_stats(file_paths, dest_file, opts).map(|(converted_hdt, _)| converted_hdt)
+}
+
+/// Same as [`build_hdt`], additionally returning [`BuildStats`] for the conversion.
+pub fn build_hdt_with_stats(
+    file_paths: Vec<String>,
+    dest_file: &str,
+    opts: &Options,
+) -> Result<(hdt::Hdt, BuildStats), Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    let sources = opts.emit_manifest.as_ref().map(|_| file_paths.clone());
+    let (converted_hdt, mut stats, order) = convert(file_paths, opts, opts.emit_order_index)?;
+    write_hdt_file(&converted_hdt, &stats, dest_file, opts)?;
+    if let Some(order) = order {
+        write_order_index(&order, dest_file)?;
+    }
+
+    stats.elapsed = timer.elapsed();
+    debug!("Total execution time: {:?}", stats.elapsed);
+    if let (Some(manifest), Some(sources)) = (&opts.emit_manifest, sources) {
+        write_manifest(manifest, &sources, dest_file, &stats, opts)?;
+    }
+    Ok((converted_hdt, stats))
+}
+
+/// Converts each of the `file_paths` to an HDT of its own in `dest_dir`,
+/// returning the result of every conversion in input order.
+///
+/// A failed conversion doesn't stop the others. Each HDT is named after its
+/// input without the RDF and `.gz` extensions, e.g. `data.hdt` for
+/// `dir/data.ttl.gz`; an input that would overwrite the HDT of an earlier one,
+/// and stdin, are errors. Files are converted one after the other, with the same
+/// `opts`.
+pub fn build_many(
+    file_paths: Vec<String>,
+    dest_dir: &Path,
+    opts: &Options,
+) -> Vec<(String, Result<BuildStats, Rdf2HdtError>)> {
+    let mut dests = HashMap::new();
+    file_paths
+        .into_iter()
+        .map(|file| {
+            let dest = many_dest(&file, dest_dir).and_then(|dest| {
+                match dests.insert(dest.clone(), file.clone()) {
+                    Some(earlier) => Err(Rdf2HdtError::InvalidInput(format!(
+                        "{file} and {earlier} would both be converted to {}",
+                        dest.display()
+                    ))),
+                    None => Ok(dest),
+                }
+            });
+            let result = dest.and_then(|dest| {
+                let dest = dest.to_str().ok_or_else(|| {
+                    Rdf2HdtError::InvalidInput(format!("{} is not a UTF-8 path", dest.display()))
+                })?;
+                build_hdt_with_stats(vec![file.clone()], dest, opts).map(|(_, stats)| stats)
+            });
+            if let Err(e) = &result {
+                error!("failed to convert {file}: {e}");
+            }
+            (file, result)
+        })
+        .collect()
+}
+
+/// The HDT [`build_many`] converts `file` to.
+fn many_dest(file: &str, dest_dir: &Path) -> Result<PathBuf, Rdf2HdtError> {
+    if file == STDIN_PATH {
+        return Err(Rdf2HdtError::InvalidInput(
+            "stdin can't be converted by build_many".to_string(),
+        ));
+    }
+    let name = Path::new(file.strip_suffix(".gz").unwrap_or(file));
+    let stem = name.file_stem().ok_or_else(|| {
+        Rdf2HdtError::InvalidInput(format!("{file} has no file name to name its HDT after"))
+    })?;
+    let mut hdt_name = stem.to_os_string();
+    hdt_name.push(".hdt");
+    Ok(dest_dir.join(hdt_name))
+}
+
+/// Same as [`build_hdt`], with the input given as glob patterns such as
+/// `data/**/*.ttl.gz`.
+///
+/// The files matched by all the patterns are converted together, in sorted order,
+/// each file once even if several patterns match it. A pattern that matches no
+/// file is an error, listing every such pattern.
+pub fn build_hdt_glob(
+    patterns: Vec<String>,
+    dest_file: &str,
+    opts: &Options,
+) -> Result<hdt::Hdt, Rdf2HdtError> {
+    build_hdt(expand_globs(&patterns)?, dest_file, opts)
+}
+
+fn expand_globs(patterns: &[String]) -> Result<Vec<String>, Rdf2HdtError> {
+    let mut file_paths = Vec::new();
+    let mut unmatched = Vec::new();
+    for pattern in patterns {
+        let matches = glob::glob(pattern).map_err(|e| {
+            Rdf2HdtError::InvalidInput(format!("invalid glob pattern {pattern}: {e}"))
+        })?;
+        let before = file_paths.len();
+        for path in matches {
+            let path = path.map_err(|e| Rdf2HdtError::Io(e.into_error()))?;
+            if !path.is_file() {
+                continue;
+            }
+            let path = path.into_os_string().into_string().map_err(|path| {
+                Rdf2HdtError::InvalidInput(format!(
+                    "{} matched by {pattern} is not a UTF-8 path",
+                    Path::new(&path).display()
+                ))
+            })?;
+            file_paths.push(path);
+        }
+        if file_paths.len() == before {
+            unmatched.push(pattern.as_str());
+        }
+    }
+    if !unmatched.is_empty() {
+        error!("no files match {}", unmatched.join(", "));
+        return Err(Rdf2HdtError::InvalidInput(format!(
+            "no files match the patterns {}",
+            unmatched.join(", ")
+        )));
+    }
+    file_paths.sort_unstable();
+    file_paths.dedup();
+    Ok(file_paths)
+}
+
+/// Converts the RDF input like [`build_hdt`], and returns the HDT without
+/// writing it anywhere.
+///
+/// Intermediate files are still written to [`Options::temp_dir`], unless the
+/// input is a single `.nt` file read directly by the hdt crate. The header
+/// statistics and [`Options::header_metadata`] are only added while writing, so
+/// they are missing from the returned HDT, and the options about the written
+/// file, such as [`Options::verify`] and [`Options::emit_checksum`], are ignored.
+pub fn build_hdt_in_memory(
+    file_paths: Vec<String>,
+    opts: &Options,
+) -> Result<hdt::Hdt, Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    let (converted_hdt, _, _) = convert(file_paths, opts, false)?;
+    debug!("Total execution time: {:?}", timer.elapsed());
+    Ok(converted_hdt)
+}
+
+/// Converts the RDF input to HDT and writes it to `writer` instead of a file.
+///
+/// Writes are buffered according to [`Options::write_buffer_size`].
+pub fn build_hdt_to_writer(
+    file_paths: Vec<String>,
+    writer: impl Write,
+    opts: &Options,
+) -> Result<hdt::Hdt, Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    let (converted_hdt, stats, _) = convert(file_paths, opts, false)?;
+    write_hdt(&converted_hdt, &stats, writer, opts)?;
+    debug!("Total execution time: {:?}", timer.elapsed());
+    Ok(converted_hdt)
+}
+
+/// Converts RDF held in memory to HDT, and writes it to `writer`.
+///
+/// The RDF is parsed as `format`, with the same options as in [`build_hdt`]. It
+/// has no base IRI unless [`Options::base_iri`] is set. Only the intermediate
+/// N-Triples file handed to the hdt crate is written to disk, in
+/// [`Options::temp_dir`]. Writes are buffered according to
+/// [`Options::write_buffer_size`].
+pub fn build_hdt_from_str(
+    rdf: &str,
+    format: RdfFormat,
+    writer: impl Write,
+    opts: &Options,
+) -> Result<hdt::Hdt, Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    check_dictionary_format(opts)?;
+    let mut reader = FileReader::from_bytes(rdf.as_bytes().to_vec(), format, opts);
+    let (converted_hdt, input_triples, _) =
+        hdt_from_nt(opts, |nt_file| write_nt(&mut reader, nt_file, opts))?;
+    let stats = BuildStats {
+        skipped: reader.stats.skipped,
+        excluded: reader.stats.excluded,
+        not_included: reader.stats.not_included,
+        filtered: reader.stats.filtered,
+        pruned_terms: pruned_terms(&converted_hdt, &reader.stats),
+        warnings: std::mem::take(&mut reader.stats.warnings),
+        input_bytes: rdf.len() as u64,
+        ..collect_stats(&converted_hdt, Some(input_triples))
+    };
+    report_build_phases(&stats, opts);
+    write_hdt(&converted_hdt, &stats, writer, opts)?;
+    debug!("Total execution time: {:?}", timer.elapsed());
+    Ok(converted_hdt)
+}
+
+/// Converts the RDF input like [`build_hdt`], without writing the HDT, and
+/// returns the terms of its dictionary as their section and term.
+///
+/// Terms come in dictionary order, sorted within each section, so the `n`th
+/// term of a section has the `n`th ID of [`Section::ids`](crate::dictionary::Section::ids).
+/// A term used both as subject and object is only returned once, in the shared
+/// section. The HDT is kept until the iterator is dropped, see
+/// [`DictionaryTerms::hdt`].
+pub fn sorted_terms(
+    file_paths: Vec<String>,
+    opts: &Options,
+) -> Result<DictionaryTerms, Rdf2HdtError> {
+    let (converted_hdt, _, _) = convert(file_paths, opts, false)?;
+    Ok(DictionaryTerms::new(converted_hdt))
+}
+
+/// Converts the RDF input like [`build_hdt`], but splits the output into HDT
+/// files of about [`Options::max_output_triples_per_file`] triples, and returns
+/// their paths.
+///
+/// The files are named after `dest_file`, e.g. `data.0.hdt`, `data.1.hdt`, ...
+/// for `data.hdt`. Triples are split by ranges of subjects in dictionary order,
+/// so the triples of a subject are always in the same file, which can make it
+/// larger than the maximum. Each file is self-contained, with a dictionary of its
+/// own, so terms used in several files are repeated in each, and blank nodes
+/// are relabeled. Without a maximum, a single HDT is written to `dest_file`.
+pub fn build_hdt_shards(
+    file_paths: Vec<String>,
+    dest_file: &str,
+    opts: &Options,
+) -> Result<Vec<PathBuf>, Rdf2HdtError> {
+    let Some(max) = opts.max_output_triples_per_file else {
+        build_hdt(file_paths, dest_file, opts)?;
+        return Ok(vec![PathBuf::from(dest_file)]);
+    };
+    if max == 0 {
+        return Err(Rdf2HdtError::InvalidInput(
+            "max_output_triples_per_file must be at least 1".to_string(),
+        ));
+    }
+    let timer = std::time::Instant::now();
+    let (converted_hdt, _, _) = convert(file_paths, opts, false)?;
+    let stem = dest_file.strip_suffix(".hdt").unwrap_or(dest_file);
+    let mut reader = HdtReader::new(&converted_hdt);
+    let mut next = None;
+    let mut paths = Vec::new();
+    loop {
+        if next.is_none() {
+            match reader.next_triple() {
+                Some(triple) => next = Some(triple?),
+                None => break,
+            }
+        }
+        let mut shard = Shard::new(&mut reader, &mut next, max);
+        let (shard_hdt, triples, _) =
+            hdt_from_nt(opts, |nt_file| write_nt(&mut shard, nt_file, opts))?;
+        let path = format!("{stem}.{}.hdt", paths.len());
+        write_hdt_file(
+            &shard_hdt,
+            &collect_stats(&shard_hdt, Some(triples)),
+            &path,
+            opts,
+        )?;
+        debug!("wrote {triples} triples to {path}");
+        paths.push(PathBuf::from(path));
+    }
+    debug!(
+        "Total execution time: {:?}, {} files",
+        timer.elapsed(),
+        paths.len()
+    );
+    Ok(paths)
+}
+
+/// Parses the RDF input without building an HDT, to check that it converts.
+///
+/// The input goes through the same readers as in [`build_hdt`], including for a
+/// single `.nt` file, and the first error is returned. Only
+/// [`BuildStats::input_triples`], [`BuildStats::skipped`], the filter counts,
+/// [`BuildStats::input_bytes`] and [`BuildStats::elapsed`] are set, duplicates
+/// and terms are only counted once the HDT is built.
+pub fn dry_run(file_paths: Vec<String>, opts: &Options) -> Result<BuildStats, Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    check_inputs(&file_paths, opts)?;
+    let input_bytes = input_size(&file_paths);
+    let (input_triples, read_stats) = write_files_nt(file_paths, std::io::sink(), opts)?;
+    let stats = BuildStats {
+        input_triples,
+        skipped: read_stats.skipped,
+        excluded: read_stats.excluded,
+        not_included: read_stats.not_included,
+        filtered: read_stats.filtered,
+        warnings: read_stats.warnings,
+        input_bytes,
+        elapsed: timer.elapsed(),
+        ..Default::default()
+    };
+    debug!(
+        "Parsed {input_triples} triples in {:?}, without building the HDT",
+        stats.elapsed
+    );
+    Ok(stats)
+}
+
+/// Counts the distinct triples and terms of the RDF input without building an HDT.
+///
+/// Terms are only interned in a hash map rather than sorted and compressed into
+/// a dictionary, which is much faster, but takes memory for every distinct term.
+/// The counts are the ones [`build_hdt_with_stats`] would report.
+pub fn count_terms(file_paths: Vec<String>, opts: &Options) -> Result<BuildStats, Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    check_inputs(&file_paths, opts)?;
+    let input_bytes = input_size(&file_paths);
+    let mut file_paths = file_paths;
+    file_paths.sort_unstable();
+    let mut reader = FileReader::new(file_paths, opts);
+
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut intern = |term: String| {
+        let next = ids.len();
+        *ids.entry(term).or_insert(next)
+    };
+    let mut subjects = HashSet::new();
+    let mut predicates = HashSet::new();
+    let mut objects = HashSet::new();
+    let mut triples = HashSet::new();
+    let mut input_triples: u64 = 0;
+    while let Some(triple) = reader.next_triple() {
+        let triple = triple?;
+        let s = intern(hdt_string(&triple.subject.into()));
+        let p = intern(hdt_string(&triple.predicate.into()));
+        let o = intern(hdt_string(&triple.object));
+        subjects.insert(s);
+        predicates.insert(p);
+        objects.insert(o);
+        triples.insert((s, p, o));
+        input_triples += 1;
+        if input_triples.is_multiple_of(PROGRESS_INTERVAL) {
+            opts.report_progress(BuildPhase::Parsing, input_triples);
+        }
+    }
+    opts.report_progress(BuildPhase::Parsing, input_triples);
+
+    let mut predicate_histogram = Vec::new();
+    if opts.emit_predicate_histogram {
+        let mut counts: HashMap<usize, u64> = HashMap::new();
+        for (_, p, _) in &triples {
+            *counts.entry(*p).or_default() += 1;
+        }
+        predicate_histogram = ids
+            .iter()
+            .filter_map(|(term, id)| counts.get(id).map(|count| (term.clone(), *count)))
+            .collect();
+        predicate_histogram.sort_unstable();
+    }
+    let triples = triples.len() as u64;
+    let stats = BuildStats {
+        input_triples,
+        skipped: reader.stats.skipped,
+        excluded: reader.stats.excluded,
+        not_included: reader.stats.not_included,
+        filtered: reader.stats.filtered,
+        pruned_terms: reader
+            .stats
+            .dropped_terms
+            .iter()
+            .filter(|term| !ids.contains_key(*term))
+            .count() as u64,
+        warnings: std::mem::take(&mut reader.stats.warnings),
+        input_bytes,
+        duplicates: input_triples - triples,
+        triples,
+        subjects: subjects.len() as u64,
+        predicates: predicates.len() as u64,
+        objects: objects.len() as u64,
+        shared: subjects.intersection(&objects).count() as u64,
+        elapsed: timer.elapsed(),
+        predicate_histogram,
+    };
+    debug!(
+        "Counted {} distinct terms in {:?}",
+        ids.len(),
+        stats.elapsed
+    );
+    Ok(stats)
+}
+
+/// Normalizes the RDF input to N-Triples, without building an HDT, and returns
+/// the number of triples written.
+///
+/// Any supported format can be read, and the input goes through the same
+/// readers and options as in [`build_hdt`], so the output is the N-Triples the
+/// HDT would be built from: in input order, duplicates included. Use
+/// [`write_sorted_nt`] for sorted, deduplicated output. Writes are buffered, and
+/// `writer` is flushed once all triples are written.
+pub fn convert_to_nt(
+    file_paths: Vec<String>,
+    writer: impl Write,
+    opts: &Options,
+) -> Result<u64, Rdf2HdtError> {
+    check_inputs(&file_paths, opts)?;
+    let (count, read_stats) = write_files_nt(file_paths, writer, opts)?;
+    debug!(
+        "Wrote {count} triples as N-Triples, skipped {}",
+        read_stats.skipped
+    );
+    Ok(count)
+}
+
+/// Writes the RDF input to `writer` as sorted, deduplicated N-Triples, without
+/// building an HDT, and returns the number of triples written.
+///
+/// Every input, including N-Triples, is parsed and serialized again, so terms are
+/// escaped the same way whatever the source. Lines are sorted by their bytes and
+/// held in memory while sorting. Blank node labels take part in the ordering,
+/// set [`Options::canonical_bnodes`] for output that can be compared across runs.
+pub fn write_sorted_nt(
+    file_paths: Vec<String>,
+    mut writer: impl Write,
+    opts: &Options,
+) -> Result<u64, Rdf2HdtError> {
+    check_inputs(&file_paths, opts)?;
+    let nt_tmp = opts.temp_file(".nt")?;
+    write_files_nt(file_paths, nt_tmp.reopen()?, opts)?;
+    let nt = std::fs::read_to_string(nt_tmp.path())?;
+    drop(nt_tmp);
+
+    let mut lines: Vec<&str> = nt.lines().collect();
+    lines.sort_unstable();
+    lines.dedup();
+    for line in &lines {
+        writeln!(writer, "{line}")?;
+    }
+    writer.flush()?;
+    Ok(lines.len() as u64)
+}
+
+/// Counts collected by [`append_to_hdt`].
+#[derive(Debug, Clone, Default)]
+pub struct AppendStats {
+    /// Counts for the combined HDT.
+    ///
+    /// [`BuildStats::input_triples`] includes the triples of the existing HDT, and
+    /// new triples already in it are counted as [`BuildStats::duplicates`].
+    pub build: BuildStats,
+    /// Dictionary entries carried over from the existing HDT.
+    pub reused_terms: u64,
+    /// Dictionary entries added for terms only found in the new input.
+    pub added_terms: u64,
+}
+
+/// Adds the triples of `new_files` to the HDT at `existing`, writing the combined
+/// HDT to `dest_file`, which may be `existing` itself.
+///
+/// The hdt crate cannot extend a dictionary in place, so the HDT is rebuilt, but
+/// its triples are read back from the existing HDT rather than by re-parsing the
+/// RDF it was built from. Blank nodes of the existing HDT are relabeled and never
+/// merged with those of the new input.
+pub fn append_to_hdt(
+    existing: &Path,
+    mut new_files: Vec<String>,
+    dest_file: &str,
+    opts: &Options,
+) -> Result<(hdt::Hdt, AppendStats), Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    check_inputs(&new_files, opts)?;
+    check_dictionary_format(opts)?;
+    let input_bytes = input_size(&new_files);
+    let existing_hdt = hdt::Hdt::read(BufReader::new(File::open(existing)?))?;
+    let reused_terms = dictionary_size(&collect_stats(&existing_hdt, None));
+    new_files.sort_unstable();
+
+    let mut reader = Chain(
+        HdtReader::new(&existing_hdt),
+        // apart from the relabeled blank nodes of the existing HDT
+        FileReader::new(new_files, opts).with_scoped_bnodes(),
+    );
+    let (converted_hdt, input_triples, _) =
+        hdt_from_nt(opts, |nt_file| write_nt(&mut reader, nt_file, opts))?;
+    let mut stats = BuildStats {
+        skipped: reader.1.stats.skipped,
+        excluded: reader.1.stats.excluded,
+        not_included: reader.1.stats.not_included,
+        filtered: reader.1.stats.filtered,
+        pruned_terms: pruned_terms(&converted_hdt, &reader.1.stats),
+        warnings: std::mem::take(&mut reader.1.stats.warnings),
+        input_bytes,
+        predicate_histogram: predicate_histogram(&converted_hdt, opts),
+        ..collect_stats(&converted_hdt, Some(input_triples))
+    };
+    report_build_phases(&stats, opts);
+    write_hdt_file(&converted_hdt, &stats, dest_file, opts)?;
+
+    stats.elapsed = timer.elapsed();
+    debug!("Total execution time: {:?}", stats.elapsed);
+    let added_terms = dictionary_size(&stats).saturating_sub(reused_terms);
+    Ok((
+        converted_hdt,
+        AppendStats {
+            build: stats,
+            reused_terms,
+            added_terms,
+        },
+    ))
+}
+
+fn check_inputs(file_paths: &[String], opts: &Options) -> Result<(), Rdf2HdtError> {
     if file_paths.is_empty() {
         error!("no files provided");
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
-            "no files provided to convert",
-        )
-        .into());
+        return Err(Rdf2HdtError::InvalidInput(
+            "no files provided to convert".to_string(),
+        ));
+    }
+    if file_paths.iter().any(|f| f == STDIN_PATH) {
+        if file_paths.len() > 1 {
+            error!("stdin cannot be combined with other input files");
+            return Err(Rdf2HdtError::InvalidInput(
+                "stdin ('-') cannot be combined with other input files".to_string(),
+            ));
+        }
+        if opts.input_format.is_none() {
+            error!("no format provided for stdin");
+            return Err(Rdf2HdtError::UnsupportedFormat(
+                "a format is required when reading RDF from stdin".to_string(),
+            ));
+        }
     }
+    Ok(())
+}
+
+fn check_dictionary_format(opts: &Options) -> Result<(), Rdf2HdtError> {
+    match opts.dictionary_format {
+        DictFormat::FourSectionPlain => Ok(()),
+        format => {
+            error!("unsupported dictionary format {format:?}");
+            Err(Rdf2HdtError::UnsupportedFormat(format!(
+                "the {format:?} dictionary format is not supported, only FourSectionPlain"
+            )))
+        }
+    }
+}
+
+/// Builds the HDT, and its [`Options::emit_order_index`] if `with_order` is set.
+fn convert(
+    file_paths: Vec<String>,
+    opts: &Options,
+    with_order: bool,
+) -> Result<(hdt::Hdt, BuildStats, Option<Vec<u64>>), Rdf2HdtError> {
+    check_inputs(&file_paths, opts)?;
+    check_dictionary_format(opts)?;
+    let input_bytes = input_size(&file_paths);
 
     let timer = std::time::Instant::now();
-    let mut used_tmp = false;
-    let nt_file = if file_paths.len() == 1 && file_paths[0].ends_with(".nt") {
-        file_paths[0].clone()
+    let nt_scan =
+        if file_paths.len() == 1 && file_paths[0].ends_with(".nt") && !opts.transforms_input() {
+            opts.check_cancelled()?;
+            // the hdt crate doesn't validate IRIs, raw spaces or control characters
+            // would end up in the dictionary
+            let nt_scan = scan_nt_file(Path::new(&file_paths[0])).inspect_err(|e| {
+                error!("invalid N-Triples in {}: {e}", file_paths[0]);
+            })?;
+            // what the hdt crate doesn't read the way rdf2hdt does is left to the
+            // reader, parsing the file once more
+            Some(nt_scan).filter(|nt_scan| !nt_scan.needs_reader)
+        } else {
+            None
+        };
+    let (converted_hdt, input_triples, read_stats, order) = if let Some(nt_scan) = nt_scan {
+        opts.check_cancelled()?;
+        let nt_file = Path::new(&file_paths[0]);
+        let input_triples = nt_scan.triples;
+        let converted_hdt = if input_triples > 0 {
+            read_nt(nt_file)?
+        } else {
+            empty_hdt(opts)?
+        };
+        log_hdt_build(&converted_hdt, timer);
+        let order = with_order
+            .then(|| order_index(&converted_hdt, nt_file))
+            .transpose()?;
+        (converted_hdt, input_triples, ReadStats::default(), order)
     } else {
-        used_tmp = true;
-        let tmp_file = tempfile::Builder::new()
-            .disable_cleanup(true)
-            .suffix(".nt")
-            .tempfile()?;
-        convert_to_nt(file_paths, tmp_file.reopen()?).expect("failed to convert file to NT");
-        tmp_file.path().to_str().unwrap().to_string()
+        let mut stats = ReadStats::default();
+        let (converted_hdt, input_triples, nt_tmp) = hdt_from_nt(opts, |nt_file| {
+            let (input_triples, read_stats) = write_files_nt(file_paths, nt_file, opts)?;
+            stats = read_stats;
+            Ok(input_triples)
+        })?;
+        let order = with_order
+            .then(|| order_index(&converted_hdt, nt_tmp.path()))
+            .transpose()?;
+        (converted_hdt, input_triples, stats, order)
     };
 
-    let converted_hdt = hdt::Hdt::read_nt(std::path::Path::new(&nt_file))?;
-
     debug!("HDT build time: {:?}", timer.elapsed());
+    let stats = BuildStats {
+        skipped: read_stats.skipped,
+        excluded: read_stats.excluded,
+        not_included: read_stats.not_included,
+        filtered: read_stats.filtered,
+        pruned_terms: pruned_terms(&converted_hdt, &read_stats),
+        warnings: read_stats.warnings,
+        input_bytes,
+        predicate_histogram: predicate_histogram(&converted_hdt, opts),
+        ..collect_stats(&converted_hdt, Some(input_triples))
+    };
+    report_build_phases(&stats, opts);
+    Ok((converted_hdt, stats, order))
+}
+
+/// Maps each triple of the HDT, in HDT order, to the position of the first
+/// matching triple in the N-Triples file it was built from.
+fn order_index(converted_hdt: &hdt::Hdt, nt_path: &Path) -> Result<Vec<u64>, Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    let id = |term: &oxrdf::Term, role| {
+        id_for_term(converted_hdt, &hdt_string(term), role).ok_or_else(|| {
+            Rdf2HdtError::Verification(format!("{term} is missing from the HDT dictionary"))
+        })
+    };
+    let mut triples = Vec::new();
+    let parser = oxrdfio::RdfParser::from_format(RdfFormat::NTriples);
+    for (position, quad) in parser
+        .for_reader(BufReader::new(File::open(nt_path)?))
+        .enumerate()
+    {
+        let quad = quad?;
+        let subject = oxrdf::Term::from(quad.subject);
+        triples.push((
+            id(&subject, TermRole::Subject)?,
+            id(&quad.predicate.into(), TermRole::Predicate)?,
+            id(&quad.object, TermRole::Object)?,
+            position as u64,
+        ));
+    }
+    // HDT triples are sorted by subject, predicate and object ID
+    triples.sort_unstable();
+    triples.dedup_by_key(|(s, p, o, _)| (*s, *p, *o));
+    debug!(
+        "Order index time: {:?}, {} triples",
+        timer.elapsed(),
+        triples.len()
+    );
+    Ok(triples
+        .into_iter()
+        .map(|(_, _, _, position)| position)
+        .collect())
+}
 
+/// Writes the [`Options::emit_order_index`] of the HDT written to `dest_file`.
+fn write_order_index(order: &[u64], dest_file: &str) -> Result<(), Rdf2HdtError> {
+    let path = Path::new(dest_file).with_extension("order");
+    let mut writer = BufWriter::new(File::create(&path)?);
+    for position in order {
+        writeln!(writer, "{position}")?;
+    }
+    writer.flush()?;
+    debug!("wrote the order index to {}", path.display());
+    Ok(())
+}
+
+/// The HDT built from an input without triples, see [`Options::allow_empty`].
+fn empty_hdt(opts: &Options) -> Result<hdt::Hdt, Rdf2HdtError> {
+    if !opts.allow_empty {
+        error!("no triples found in the input");
+        return Err(Rdf2HdtError::EmptyInput);
+    }
+    Ok(hdt::Hdt::read(std::io::Cursor::new(
+        crate::empty_hdt::empty_hdt(),
+    ))?)
+}
+
+fn input_size(file_paths: &[String]) -> u64 {
+    file_paths
+        .iter()
+        .filter(|f| *f != STDIN_PATH)
+        .filter_map(|f| std::fs::metadata(f).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Builds the HDT from an intermediate N-Triples file filled by `write`, which
+/// returns the number of triples it wrote. The file is returned too, and deleted
+/// when dropped.
+fn hdt_from_nt(
+    opts: &Options,
+    write: impl FnOnce(File) -> Result<u64, Rdf2HdtError>,
+) -> Result<(hdt::Hdt, u64, tempfile::NamedTempFile), Rdf2HdtError> {
+    // dropping the temp file deletes it, including on the error paths below
+    let nt_tmp = opts.temp_file(".nt")?;
+    let timer = std::time::Instant::now();
+    let input_triples = write(nt_tmp.reopen()?)?;
+    debug!(
+        "Parsing time: {:?}, {input_triples} triples, {} bytes of N-Triples",
+        timer.elapsed(),
+        nt_tmp.as_file().metadata()?.len()
+    );
+    opts.check_cancelled()?;
+    let timer = std::time::Instant::now();
+    let converted_hdt = if input_triples > 0 {
+        read_nt(nt_tmp.path())?
+    } else {
+        empty_hdt(opts)?
+    };
+    log_hdt_build(&converted_hdt, timer);
+    if opts.keep_intermediate {
+        debug!("keeping intermediate file {}", nt_tmp.path().display());
+    }
+    Ok((converted_hdt, input_triples, nt_tmp))
+}
+
+/// Builds the HDT of an N-Triples file with the hdt crate.
+///
+/// If that fails, the file is parsed again to report the line and byte offset of
+/// the first invalid triple, if any.
+fn read_nt(nt_file: &Path) -> Result<hdt::Hdt, Rdf2HdtError> {
+    hdt::Hdt::read_nt(nt_file).or_else(|e| {
+        error!("the hdt crate failed to read {}: {e}", nt_file.display());
+        Err(locate_nt_error(nt_file)?.unwrap_or(e.into()))
+    })
+}
+
+/// Number of terms of the dropped triples that are not in the dictionary.
+fn pruned_terms(converted_hdt: &hdt::Hdt, read_stats: &ReadStats) -> u64 {
+    read_stats
+        .dropped_terms
+        .iter()
+        .filter(|term| term_sections(converted_hdt, term).is_empty())
+        .count() as u64
+}
+
+/// The [`BuildStats::predicate_histogram`] of `converted_hdt`, if requested.
+fn predicate_histogram(converted_hdt: &hdt::Hdt, opts: &Options) -> Vec<(String, u64)> {
+    if !opts.emit_predicate_histogram {
+        return Vec::new();
+    }
+    (1..=converted_hdt.dict.predicates.num_strings)
+        .filter_map(|id| term_for_id(converted_hdt, id, TermRole::Predicate))
+        .map(|predicate| {
+            let count = converted_hdt
+                .triples_with_pattern(None, Some(&predicate), None)
+                .count() as u64;
+            (predicate, count)
+        })
+        .collect()
+}
+
+/// Logs the time taken by the hdt crate to sort the triples and build the
+/// dictionary and triples sections, which it does in a single call.
+fn log_hdt_build(converted_hdt: &hdt::Hdt, timer: std::time::Instant) {
+    debug!(
+        "Sorting, dictionary and triples build time: {:?}, HDT size in memory: {} bytes",
+        timer.elapsed(),
+        converted_hdt.size_in_bytes()
+    );
+}
+
+/// Reports the phases run by the hdt crate, which only complete once it returns.
+fn report_build_phases(stats: &BuildStats, opts: &Options) {
+    opts.report_progress(BuildPhase::SortingTriples, stats.triples);
+    opts.report_progress(BuildPhase::BuildingDictionary, dictionary_size(stats));
+    opts.report_progress(BuildPhase::BuildingTriples, stats.triples);
+}
+
+/// Number of dictionary entries, shared terms are only counted once.
+fn dictionary_size(stats: &BuildStats) -> u64 {
+    stats.subjects + stats.predicates + stats.objects - stats.shared
+}
+
+fn write_hdt_file(
+    converted_hdt: &hdt::Hdt,
+    stats: &BuildStats,
+    dest_file: &str,
+    opts: &Options,
+) -> Result<(), Rdf2HdtError> {
+    opts.check_cancelled()?;
     let out_file = OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
         .open(dest_file)?;
-    let mut writer = BufWriter::new(out_file);
-    converted_hdt.write(&mut writer)?;
-    writer.flush()?;
-    if used_tmp {
-        let _ = std::fs::remove_file(nt_file);
+    if opts.emit_checksum {
+        let mut writer = Sha256Writer::new(out_file);
+        write_hdt(converted_hdt, stats, &mut writer, opts)?;
+        let (_, digest) = writer.finish();
+        let sidecar = write_sha256_file(Path::new(dest_file), &digest)?;
+        debug!("wrote the SHA-256 of {dest_file} to {}", sidecar.display());
+    } else {
+        write_hdt(converted_hdt, stats, out_file, opts)?;
     }
+    if opts.verify {
+        verify_hdt_file(converted_hdt, dest_file)?;
+    }
+    Ok(())
+}
 
-    debug!("Total execution time: {:?}", timer.elapsed());
-    Ok(converted_hdt)
+/// Re-reads `dest_file` and checks that its triples are those of `expected`.
+fn verify_hdt_file(expected: &hdt::Hdt, dest_file: &str) -> Result<(), Rdf2HdtError> {
+    let timer = std::time::Instant::now();
+    let written = hdt::Hdt::read(BufReader::new(File::open(dest_file)?))?;
+    // both HDTs list their triples in SPO order, so they must match one by one
+    let mut expected_triples = expected.triples_with_pattern(None, None, None);
+    let mut written_triples = written.triples_with_pattern(None, None, None);
+    let mut index = 0;
+    loop {
+        match (expected_triples.next(), written_triples.next()) {
+            (None, None) => break,
+            (Some(e), Some(w)) if e == w => {}
+            (Some(e), Some(w)) => {
+                return Err(Rdf2HdtError::Verification(format!(
+                    "triple {index} of {dest_file} is {}, expected {}",
+                    w.join(" "),
+                    e.join(" ")
+                )));
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(Rdf2HdtError::Verification(format!(
+                    "{dest_file} has {} triples, expected {}",
+                    written.triples.adjlist_z.len(),
+                    expected.triples.adjlist_z.len()
+                )));
+            }
+        }
+        index += 1;
+    }
+    debug!("verified {index} triples in {:?}", timer.elapsed());
+    Ok(())
+}
+
+fn write_hdt(
+    converted_hdt: &hdt::Hdt,
+    stats: &BuildStats,
+    writer: impl Write,
+    opts: &Options,
+) -> Result<(), Rdf2HdtError> {
+    opts.check_cancelled()?;
+    let timer = std::time::Instant::now();
+    let properties = HeaderProperties::new(stats, opts)?;
+    opts.report_progress(BuildPhase::Writing, 0);
+    let buffer_size = opts.write_buffer_size.unwrap_or(DEFAULT_WRITE_BUFFER_SIZE);
+    let mut writer = HeaderWriter::new(BufWriter::with_capacity(buffer_size, writer), properties);
+    converted_hdt.write(&mut writer)?;
+    writer.finish()?.flush()?;
+    opts.report_progress(BuildPhase::Writing, stats.triples);
+    debug!("Writing time: {:?}", timer.elapsed());
+    Ok(())
+}
+
+fn collect_stats(converted_hdt: &hdt::Hdt, input_triples: Option<u64>) -> BuildStats {
+    let dict = &converted_hdt.dict;
+    let shared = dict.shared.num_strings as u64;
+    let triples = converted_hdt.triples.adjlist_z.len() as u64;
+    let input_triples = input_triples.unwrap_or(triples);
+    BuildStats {
+        input_triples,
+        duplicates: input_triples.saturating_sub(triples),
+        triples,
+        subjects: shared + dict.subjects.num_strings as u64,
+        predicates: dict.predicates.num_strings as u64,
+        objects: shared + dict.objects.num_strings as u64,
+        shared,
+        ..Default::default()
+    }
 }
 
 #[cfg(test)]
@@ -92,7 +1645,7 @@ mod tests {
             );
             std::fs::create_dir_all(std::path::Path::new(&hdt_file_path).parent().unwrap())?;
 
-            if build_hdt(vec![f.to_string()], &hdt_file_path).is_ok() {
+            if build_hdt(vec![f.to_string()], &hdt_file_path, &Options::default()).is_ok() {
                 assert!(std::path::Path::new(&hdt_file_path).exists())
             }
         }
@@ -133,7 +1686,7 @@ mod tests {
             );
             std::fs::create_dir_all(std::path::Path::new(&hdt_file_path).parent().unwrap())?;
 
-            if build_hdt(vec![f.to_string()], &hdt_file_path).is_ok() {
+            if build_hdt(vec![f.to_string()], &hdt_file_path, &Options::default()).is_ok() {
                 assert!(std::path::Path::new(&hdt_file_path).exists())
             }
         }
@@ -174,13 +1727,1046 @@ mod tests {
             );
             std::fs::create_dir_all(std::path::Path::new(&hdt_file_path).parent().unwrap())?;
 
-            if build_hdt(vec![f.to_string()], &hdt_file_path).is_ok() {
+            if build_hdt(vec![f.to_string()], &hdt_file_path, &Options::default()).is_ok() {
                 assert!(std::path::Path::new(&hdt_file_path).exists())
             }
         }
         Ok(())
     }
 
+    #[test]
+    fn build_stats() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let (_, stats) = build_hdt_with_stats(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        assert_eq!(stats.input_triples, 9);
+        assert_eq!(stats.duplicates, 0);
+        assert_eq!(stats.triples, 9);
+        assert_eq!(stats.subjects, 2);
+        assert_eq!(stats.predicates, 7);
+        assert_eq!(stats.objects, 9);
+        assert_eq!(stats.shared, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn count_only() -> Result<(), Rdf2HdtError> {
+        let stats = count_terms(
+            vec!["tests/resources/apple.ttl".to_string()],
+            &Options::default(),
+        )?;
+        assert_eq!(stats.input_triples, 9);
+        assert_eq!(stats.triples, 9);
+        assert_eq!(stats.subjects, 2);
+        assert_eq!(stats.predicates, 7);
+        assert_eq!(stats.objects, 9);
+        assert_eq!(stats.shared, 1);
+
+        let stats = count_terms(
+            vec!["tests/resources/duplicates.ttl".to_string()],
+            &Options::default(),
+        )?;
+        assert_eq!(stats.triples, 3);
+        assert_eq!(stats.duplicates, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn bom_and_crlf() -> Result<(), Rdf2HdtError> {
+        use crate::dictionary::{Section, term_sections};
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt(
+            vec!["tests/resources/bom_crlf.nt".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        // the first subject doesn't start with U+FEFF
+        assert_eq!(
+            term_sections(&hdt, "http://example.org/bom#s"),
+            [Section::Subjects]
+        );
+        assert_eq!(Section::Subjects.ids(&hdt).count(), 1);
+        assert_eq!(hdt.triples.adjlist_z.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn late_crlf() -> Result<(), Rdf2HdtError> {
+        use crate::dictionary::{Section, term_sections};
+        let tmp_nt = tempfile::Builder::new().suffix(".nt").tempfile()?;
+        // enough LF lines to fill the first blocks of the file
+        let mut nt: String = (0..2000)
+            .map(|i| format!("<http://ex/s> <http://ex/p> <http://ex/o{i}> .\n"))
+            .collect();
+        nt.push_str("<http://ex/s> <http://ex/p> <http://ex/last> .\r\n");
+        std::fs::write(tmp_nt.path(), nt)?;
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt(
+            vec![tmp_nt.path().to_str().unwrap().to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        assert_eq!(term_sections(&hdt, "http://ex/last"), [Section::Objects]);
+        assert_eq!(hdt.triples.adjlist_z.len(), 2001);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_input() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let dest = tmp_hdt.path().to_str().unwrap();
+        for input in ["tests/resources/empty.nt", "tests/resources/comments.ttl"] {
+            let res = build_hdt(vec![input.to_string()], dest, &Options::default());
+            assert!(matches!(res, Err(Rdf2HdtError::EmptyInput)), "{input}");
+
+            let opts = Options {
+                allow_empty: true,
+                ..Default::default()
+            };
+            let (hdt, stats) = build_hdt_with_stats(vec![input.to_string()], dest, &opts)?;
+            assert_eq!(hdt.triples.adjlist_z.len(), 0);
+            assert_eq!(stats.triples, 0);
+            let written = hdt::Hdt::read(BufReader::new(File::open(dest)?))?;
+            assert_eq!(written.triples_with_pattern(None, None, None).count(), 0);
+            assert_eq!(crate::integrity::verify_section_crcs(dest)?, []);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prefix_redefinition_warnings() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let (_, stats) = build_hdt_with_stats(
+            vec!["tests/resources/redefined_prefix.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        // the output is unchanged, the second ex:a is another IRI
+        assert_eq!(stats.triples, 2);
+        assert_eq!(stats.warnings.len(), 1);
+        assert!(stats.warnings[0].contains("line 5"));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_iri_nt() -> Result<(), Rdf2HdtError> {
+        let tmp_nt = tempfile::Builder::new().suffix(".nt").tempfile()?;
+        std::fs::write(
+            tmp_nt.path(),
+            "<http://ex/s> <http://ex/p> <http://ex/o> .\n\
+             <http://ex/a b> <http://ex/p> <http://ex/o> .\n",
+        )?;
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let res = build_hdt(
+            vec![tmp_nt.path().to_str().unwrap().to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        );
+        assert!(
+            matches!(res, Err(Rdf2HdtError::Parse { line: 2, .. })),
+            "{:?}",
+            res.err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn quoted_triple_nt() -> Result<(), Rdf2HdtError> {
+        let tmp_nt = tempfile::Builder::new().suffix(".nt").tempfile()?;
+        std::fs::write(
+            tmp_nt.path(),
+            "<http://ex/s> <http://ex/p> <<( <http://ex/a> <http://ex/b> <http://ex/c> )>> .\n",
+        )?;
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let res = build_hdt(
+            vec![tmp_nt.path().to_str().unwrap().to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        );
+        assert!(
+            matches!(res, Err(Rdf2HdtError::InvalidInput(_))),
+            "{:?}",
+            res.err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nul_literal_nt() -> Result<(), Rdf2HdtError> {
+        let tmp_nt = tempfile::Builder::new().suffix(".nt").tempfile()?;
+        std::fs::write(
+            tmp_nt.path(),
+            "<http://ex/s> <http://ex/p> \"a\\u0000b\" .\n\
+             <http://ex/s> <http://ex/p> \"c\" .\n",
+        )?;
+        let input = vec![tmp_nt.path().to_str().unwrap().to_string()];
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let dest = tmp_hdt.path().to_str().unwrap();
+        let res = build_hdt(input.clone(), dest, &Options::default());
+        assert!(
+            matches!(res, Err(Rdf2HdtError::InvalidInput(_))),
+            "{:?}",
+            res.err()
+        );
+
+        let opts = Options {
+            lenient: true,
+            ..Default::default()
+        };
+        build_hdt(input, dest, &opts)?;
+        let written = hdt::Hdt::read(BufReader::new(File::open(dest)?))?;
+        let objects: Vec<_> = written
+            .triples_with_pattern(None, None, None)
+            .map(|[_, _, o]| o.to_string())
+            .collect();
+        assert_eq!(objects, ["\"c\""]);
+        Ok(())
+    }
+
+    #[test]
+    fn predicate_filters() -> Result<(), Rdf2HdtError> {
+        const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+        const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let build = |opts: &Options| {
+            build_hdt_with_stats(
+                vec!["tests/resources/apple.ttl".to_string()],
+                tmp_hdt.path().to_str().unwrap(),
+                opts,
+            )
+        };
+
+        let opts = Options {
+            exclude_predicates: vec![RDFS_LABEL.to_string()],
+            ..Default::default()
+        };
+        let (hdt, stats) = build(&opts)?;
+        assert_eq!(stats.triples, 7);
+        assert_eq!(stats.excluded, 2);
+        assert_eq!(stats.not_included, 0);
+        // rdfs:label and both labels only appear in the dropped triples
+        assert_eq!(stats.pruned_terms, 3);
+        assert!(term_sections(&hdt, "\"Fruit\"").is_empty());
+        assert!(term_sections(&hdt, RDFS_LABEL).is_empty());
+
+        let opts = Options {
+            exclude_predicates: vec![RDFS_LABEL.to_string()],
+            include_only_predicates: Some(vec![RDF_TYPE.to_string(), RDFS_LABEL.to_string()]),
+            ..Default::default()
+        };
+        let (_, stats) = build(&opts)?;
+        assert_eq!(stats.triples, 2);
+        assert_eq!(stats.excluded, 2);
+        assert_eq!(stats.not_included, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn triple_filter() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        // keep the triples about ex:Apple with a literal object
+        let opts = Options {
+            triple_filter: Some(Box::new(|triple: &Triple| {
+                triple.subject.to_string() == "<http://example.org/apple#Apple>"
+                    && matches!(triple.object, oxrdf::Term::Literal(_))
+            })),
+            ..Default::default()
+        };
+        let (hdt, stats) = build_hdt_with_stats(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &opts,
+        )?;
+        assert_eq!(stats.triples, 6);
+        assert_eq!(stats.filtered, 3);
+        // ex:Fruit, rdf:type, rdfs:Class and "Fruit" only appear in dropped triples
+        assert_eq!(stats.pruned_terms, 4);
+        assert!(term_sections(&hdt, "http://example.org/apple#Fruit").is_empty());
+        assert!(term_sections(&hdt, "\"Fruit\"").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn order_index() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let order_path = tmp_hdt.path().with_extension("order");
+        // read by the hdt crate, then by rdf2hdt
+        for lenient in [false, true] {
+            let opts = Options {
+                emit_order_index: true,
+                lenient,
+                ..Default::default()
+            };
+            build_hdt(
+                vec!["tests/resources/order.nt".to_string()],
+                tmp_hdt.path().to_str().unwrap(),
+                &opts,
+            )?;
+            // subjects a, b and c, the duplicate of a is dropped
+            assert_eq!(std::fs::read_to_string(&order_path)?, "1\n2\n0\n");
+        }
+        std::fs::remove_file(order_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_triples() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let (_, stats) = build_hdt_with_stats(
+            vec!["tests/resources/duplicates.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        assert_eq!(stats.input_triples, 6);
+        assert_eq!(stats.triples, 3);
+        assert_eq!(stats.duplicates, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn verify_output() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let opts = Options {
+            verify: true,
+            ..Default::default()
+        };
+        build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &opts,
+        )?;
+
+        let other_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let other = build_hdt(
+            vec!["tests/resources/duplicates.ttl".to_string()],
+            other_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        assert!(matches!(
+            verify_hdt_file(&other, tmp_hdt.path().to_str().unwrap()),
+            Err(Rdf2HdtError::Verification(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn dry_run_parses_input() -> Result<(), Rdf2HdtError> {
+        let stats = dry_run(
+            vec!["tests/resources/duplicates.ttl".to_string()],
+            &Options::default(),
+        )?;
+        assert_eq!(stats.input_triples, 6);
+        assert_eq!(stats.triples, 0);
+
+        let err = dry_run(
+            vec!["tests/resources/invalid.ttl".to_string()],
+            &Options::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Rdf2HdtError::Parse { .. }));
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_to_nt() -> Result<(), Rdf2HdtError> {
+        let mut nt = Vec::new();
+        let count = convert_to_nt(
+            vec!["tests/resources/order.nt".to_string()],
+            &mut nt,
+            &Options::default(),
+        )?;
+        assert_eq!(count, 4);
+        assert_eq!(nt, std::fs::read("tests/resources/order.nt")?);
+
+        let mut nt = Vec::new();
+        let count = convert_to_nt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            &mut nt,
+            &Options::default(),
+        )?;
+        assert_eq!(count, 9);
+        assert_eq!(String::from_utf8(nt).unwrap().lines().count(), 9);
+        Ok(())
+    }
+
+    #[test]
+    fn sorted_nt() -> Result<(), Rdf2HdtError> {
+        let opts = Options {
+            canonical_bnodes: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let count = write_sorted_nt(
+            vec!["tests/resources/duplicates.ttl".to_string()],
+            &mut out,
+            &opts,
+        )?;
+        assert_eq!(count, 3);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<http://example.org/dup#a> <http://example.org/dup#p> <http://example.org/dup#b> .\n\
+             <http://example.org/dup#a> <http://example.org/dup#q> \"x\" .\n\
+             _:b0 <http://example.org/dup#p> <http://example.org/dup#b> .\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn append_triples() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt_path = tmp_hdt.path().to_str().unwrap();
+        let (_, base) = build_hdt_with_stats(
+            vec!["tests/resources/apple.ttl".to_string()],
+            hdt_path,
+            &Options::default(),
+        )?;
+
+        let (_, appended) = append_to_hdt(
+            tmp_hdt.path(),
+            vec!["tests/resources/bnodes_a.ttl".to_string()],
+            hdt_path,
+            &Options::default(),
+        )?;
+        assert_eq!(appended.build.input_triples, base.triples + 3);
+        assert_eq!(appended.build.triples, base.triples + 3);
+        assert_eq!(appended.reused_terms, dictionary_size(&base));
+        assert!(appended.added_terms > 0);
+
+        // appending triples that are already there adds nothing
+        let (_, again) = append_to_hdt(
+            tmp_hdt.path(),
+            vec!["tests/resources/apple.ttl".to_string()],
+            hdt_path,
+            &Options::default(),
+        )?;
+        assert_eq!(again.build.triples, appended.build.triples);
+        assert_eq!(again.build.duplicates, base.triples);
+        assert_eq!(again.added_terms, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn progress_callback() -> Result<(), Rdf2HdtError> {
+        let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let opts = Options {
+            progress: Some(Box::new(move |phase, count| {
+                recorded.lock().unwrap().push((phase, count))
+            })),
+            ..Default::default()
+        };
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &opts,
+        )?;
+        let calls = calls.lock().unwrap();
+        assert!(calls.contains(&(BuildPhase::Parsing, 9)));
+        assert!(calls.contains(&(BuildPhase::BuildingTriples, 9)));
+        assert_eq!(calls.last(), Some(&(BuildPhase::Writing, 9)));
+        Ok(())
+    }
+
+    #[test]
+    fn fluent_builder() -> Result<(), Rdf2HdtError> {
+        let builder = HdtBuilder::new()
+            .canonical_bnodes(true)
+            .parser_threads(2)
+            .base_iri("http://example.org/")
+            .header_metadata("http://purl.org/dc/terms/title", "Apples");
+        let opts = builder.options();
+        assert!(opts.canonical_bnodes);
+        assert_eq!(opts.parser_threads, Some(2));
+        assert_eq!(opts.base_iri.as_deref(), Some("http://example.org/"));
+        assert_eq!(opts.header_metadata.len(), 1);
+
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let (_, stats) = builder.build_with_stats(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+        )?;
+        assert_eq!(stats.triples, 9);
+        Ok(())
+    }
+
+    #[test]
+    fn output_shards() -> Result<(), Rdf2HdtError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let dest = tmp_dir.path().join("apple.hdt");
+        let opts = Options {
+            max_output_triples_per_file: Some(2),
+            ..Default::default()
+        };
+        let paths = build_hdt_shards(
+            vec!["tests/resources/apple.ttl".to_string()],
+            dest.to_str().unwrap(),
+            &opts,
+        )?;
+        assert_eq!(
+            paths,
+            [
+                tmp_dir.path().join("apple.0.hdt"),
+                tmp_dir.path().join("apple.1.hdt")
+            ]
+        );
+        // ex:Fruit comes first, as a shared term, then the 7 triples of ex:Apple
+        let shards = paths
+            .iter()
+            .map(|p| Ok(hdt::Hdt::read(BufReader::new(File::open(p)?))?))
+            .collect::<Result<Vec<_>, Rdf2HdtError>>()?;
+        assert_eq!(shards[0].triples.adjlist_z.len(), 2);
+        assert_eq!(shards[1].triples.adjlist_z.len(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn glob_patterns() -> Result<(), Rdf2HdtError> {
+        let patterns = vec![
+            "tests/resources/bnodes_*.ttl".to_string(),
+            "tests/resources/bnodes_a.ttl".to_string(),
+        ];
+        assert_eq!(
+            expand_globs(&patterns)?,
+            [
+                "tests/resources/bnodes_a.ttl",
+                "tests/resources/bnodes_b.ttl"
+            ]
+        );
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt_glob(
+            patterns,
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        assert_eq!(hdt.triples.adjlist_z.len(), 6);
+
+        let patterns = vec![
+            "tests/resources/*.nope".to_string(),
+            "tests/resources/bnodes_*.ttl".to_string(),
+            "missing/**/*.ttl".to_string(),
+        ];
+        match expand_globs(&patterns) {
+            Err(Rdf2HdtError::InvalidInput(msg)) => {
+                assert!(
+                    msg.ends_with("tests/resources/*.nope, missing/**/*.ttl"),
+                    "{msg}"
+                );
+            }
+            res => panic!("expected an error, got {res:?}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn build_from_str() -> Result<(), Rdf2HdtError> {
+        let ttl = "@prefix ex: <http://example.org/str#>.\n\
+                   ex:a ex:p ex:b, \"c\".\n\
+                   ex:b ex:p <d>.";
+        let opts = Options {
+            base_iri: Some("http://example.org/base/".to_string()),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let hdt = build_hdt_from_str(ttl, RdfFormat::Turtle, &mut out, &opts)?;
+        assert_eq!(hdt.triples.adjlist_z.len(), 3);
+        let written = hdt::Hdt::read(std::io::Cursor::new(out))?;
+        assert_eq!(written.triples_with_pattern(None, None, None).count(), 3);
+        assert_eq!(
+            crate::dictionary::term_sections(&written, "http://example.org/base/d"),
+            [crate::dictionary::Section::Objects]
+        );
+
+        let res = build_hdt_from_str("ex:a ex:p", RdfFormat::Turtle, std::io::sink(), &opts);
+        assert!(matches!(res, Err(Rdf2HdtError::Parse { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn sorted_apple_terms() -> Result<(), Rdf2HdtError> {
+        use crate::dictionary::Section;
+        let terms = sorted_terms(
+            vec!["tests/resources/apple.ttl".to_string()],
+            &Options::default(),
+        )?;
+        let terms = terms.collect::<Result<Vec<_>, _>>()?;
+        // 1 shared, 1 subject, 7 predicates and 8 objects
+        assert_eq!(terms.len(), 17);
+        assert_eq!(
+            terms[0],
+            (
+                Section::Shared,
+                "http://example.org/apple#Fruit".to_string()
+            )
+        );
+        for section in Section::ALL {
+            let in_section = terms.iter().filter(|(s, _)| *s == section);
+            let in_section = in_section.map(|(_, t)| t).collect::<Vec<_>>();
+            assert!(in_section.is_sorted(), "{section}: {in_section:?}");
+        }
+        assert_eq!(
+            terms.iter().filter(|(s, _)| *s == Section::Objects).count(),
+            8
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn normalized_iris() -> Result<(), Rdf2HdtError> {
+        let nt = "<http://example.org/%7euser> <http://example.org/p> <http://example.org/a%2fb> .\n\
+                  <http://example.org/~user> <http://example.org/p> <http://example.org/a%2Fb> .\n";
+        let build =
+            |opts: &Options| build_hdt_from_str(nt, RdfFormat::NTriples, std::io::sink(), opts);
+        let hdt = build(&Options::default())?;
+        assert_eq!(hdt.triples.adjlist_z.len(), 2);
+
+        let opts = Options {
+            normalize_iris: true,
+            ..Default::default()
+        };
+        let hdt = build(&opts)?;
+        assert_eq!(hdt.triples.adjlist_z.len(), 1);
+        assert_eq!(
+            term_sections(&hdt, "http://example.org/~user"),
+            [crate::dictionary::Section::Subjects]
+        );
+        assert_eq!(
+            term_sections(&hdt, "http://example.org/a%2Fb"),
+            [crate::dictionary::Section::Objects]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn trailing_slash_iris() -> Result<(), Rdf2HdtError> {
+        let nt = "<http://example.org/ns> <http://example.org/p> <http://example.org/o/> .\n\
+                  <http://example.org/ns/> <http://example.org/p> <http://example.org/o> .\n";
+        let build =
+            |opts: &Options| build_hdt_from_str(nt, RdfFormat::NTriples, std::io::sink(), opts);
+        let hdt = build(&Options::default())?;
+        assert_eq!(hdt.triples.adjlist_z.len(), 2);
+
+        let opts = Options {
+            canonical_trailing_slash: true,
+            ..Default::default()
+        };
+        let hdt = build(&opts)?;
+        assert_eq!(hdt.triples.adjlist_z.len(), 1);
+        assert_eq!(
+            term_sections(&hdt, "http://example.org/ns"),
+            [crate::dictionary::Section::Subjects]
+        );
+        assert!(term_sections(&hdt, "http://example.org/ns/").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn build_manifest() -> Result<(), Rdf2HdtError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let manifest = tmp_dir.path().join("apple.json");
+        let dest = tmp_dir.path().join("apple.hdt");
+        let opts = Options {
+            emit_manifest: Some(manifest.clone()),
+            file_formats: HashMap::from([(
+                "tests/resources/apple.ttl".to_string(),
+                RdfFormat::Turtle,
+            )]),
+            ..Default::default()
+        };
+        build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            dest.to_str().unwrap(),
+            &opts,
+        )?;
+        let json = std::fs::read_to_string(&manifest)?;
+        let apple = std::fs::read("tests/resources/apple.ttl")?;
+        let crc = format!("{:08x}", crate::integrity::CRC32C.checksum(&apple));
+        assert!(json.contains(&format!(
+            r#""sources":[{{"path":"tests/resources/apple.ttl","bytes":{},"crc32c":"{crc}"}}]"#,
+            apple.len()
+        )));
+        assert!(json.contains(r#""triples":9,"#), "{json}");
+        assert!(json.contains(r#""canonical_bnodes":false,"#), "{json}");
+        assert!(
+            json.contains(r#""file_formats":{"tests/resources/apple.ttl":"Turtle"},"#),
+            "{json}"
+        );
+        assert!(json.contains(r#""triple_filter":false,"#), "{json}");
+        assert!(json.ends_with("}\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn xsd_string_literals() -> Result<(), Rdf2HdtError> {
+        // RDF 1.1 simple literals are xsd:string literals, the parsers give both
+        // forms the same representation
+        let ttl = "@prefix xsd: <http://www.w3.org/2001/XMLSchema#>.\n\
+                   <http://example.org/a> <http://example.org/p> \"5\", \"5\"^^xsd:string.\n\
+                   <http://example.org/b> <http://example.org/p> \"5\"^^xsd:string.";
+        let hdt = build_hdt_from_str(ttl, RdfFormat::Turtle, std::io::sink(), &Options::default())?;
+        assert_eq!(hdt.triples.adjlist_z.len(), 2);
+        assert_eq!(crate::dictionary::Section::Objects.ids(&hdt).count(), 1);
+        assert_eq!(
+            term_sections(&hdt, "\"5\""),
+            [crate::dictionary::Section::Objects]
+        );
+
+        // a single .nt input is normally handed to the hdt crate as is
+        let tmp_nt = tempfile::Builder::new().suffix(".nt").tempfile()?;
+        std::fs::write(
+            tmp_nt.path(),
+            "<http://example.org/a> <http://example.org/p> \"5\" .\n\
+             <http://example.org/a> <http://example.org/p> \
+             \"5\"^^<http://www.w3.org/2001/XMLSchema#string> .\n",
+        )?;
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt(
+            vec![tmp_nt.path().to_str().unwrap().to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        assert_eq!(hdt.triples.adjlist_z.len(), 1);
+        assert_eq!(
+            term_sections(&hdt, "\"5\""),
+            [crate::dictionary::Section::Objects]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn build_many_files() -> Result<(), Rdf2HdtError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let results = build_many(
+            vec![
+                "tests/resources/apple.ttl".to_string(),
+                "tests/resources/invalid.ttl".to_string(),
+                "tests/resources/shared.ttl".to_string(),
+                "tests/resources/rdf-tests/../apple.ttl".to_string(),
+            ],
+            tmp_dir.path(),
+            &Options::default(),
+        );
+        let results = results
+            .iter()
+            .map(|(file, result)| (file.as_str(), result.as_ref().map(|s| s.triples)))
+            .collect::<Vec<_>>();
+        assert!(matches!(results[0], ("tests/resources/apple.ttl", Ok(9))));
+        assert!(matches!(results[1].1, Err(Rdf2HdtError::Parse { .. })));
+        assert!(results[2].1.is_ok());
+        assert!(matches!(results[3].1, Err(Rdf2HdtError::InvalidInput(_))));
+        assert!(tmp_dir.path().join("apple.hdt").exists());
+        assert!(tmp_dir.path().join("shared.hdt").exists());
+        assert_eq!(
+            many_dest("dir/data.ttl.gz", Path::new("out")).ok(),
+            Some(PathBuf::from("out/data.hdt"))
+        );
+        assert_eq!(
+            many_dest("data.v2.nt", Path::new("out")).ok(),
+            Some(PathBuf::from("out/data.v2.hdt"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn output_checksum() -> Result<(), Rdf2HdtError> {
+        use sha2::Digest;
+        let tmp_dir = tempfile::tempdir()?;
+        let dest = tmp_dir.path().join("apple.hdt");
+        let opts = Options {
+            emit_checksum: true,
+            ..Default::default()
+        };
+        build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            dest.to_str().unwrap(),
+            &opts,
+        )?;
+        let digest = sha2::Sha256::digest(std::fs::read(&dest)?);
+        let hex = digest
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        let sidecar = std::fs::read_to_string(tmp_dir.path().join("apple.hdt.sha256"))?;
+        assert_eq!(sidecar, format!("{hex}  apple.hdt\n"));
+
+        let mut writer = Sha256Writer::new(Vec::new());
+        build_hdt_to_writer(
+            vec!["tests/resources/apple.ttl".to_string()],
+            &mut writer,
+            &Options::default(),
+        )?;
+        let (bytes, digest) = writer.finish();
+        assert_eq!(bytes.len() as u64, std::fs::metadata(&dest)?.len());
+        assert_eq!(digest.len(), 64);
+        Ok(())
+    }
+
+    #[test]
+    fn prefixed_datatypes() -> Result<(), Rdf2HdtError> {
+        let ttl = "@prefix xsd: <http://www.w3.org/2001/XMLSchema#>.\n\
+                   @prefix ex: <http://example.org/>.\n\
+                   ex:a ex:p \"5\"^^xsd:integer.\n\
+                   ex:b ex:p \"5\"^^<http://www.w3.org/2001/XMLSchema#integer>.";
+        let hdt = build_hdt_from_str(ttl, RdfFormat::Turtle, std::io::sink(), &Options::default())?;
+        assert_eq!(crate::dictionary::Section::Objects.ids(&hdt).count(), 1);
+        assert_eq!(
+            term_sections(&hdt, "\"5\"^^<http://www.w3.org/2001/XMLSchema#integer>"),
+            [crate::dictionary::Section::Objects]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn build_in_memory() -> Result<(), Rdf2HdtError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let opts = Options {
+            temp_dir: Some(tmp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let hdt = build_hdt_in_memory(vec!["tests/resources/apple.ttl".to_string()], &opts)?;
+        assert_eq!(hdt.triples_with_pattern(None, None, None).count(), 9);
+        // the intermediate N-Triples file is deleted, and nothing else is written
+        assert_eq!(std::fs::read_dir(tmp_dir.path())?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn predicate_counts() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let files = vec!["tests/resources/apple.ttl".to_string()];
+        let dest = tmp_hdt.path().to_str().unwrap();
+        let (_, stats) = build_hdt_with_stats(files.clone(), dest, &Options::default())?;
+        assert!(stats.predicate_histogram.is_empty());
+
+        let opts = Options {
+            emit_predicate_histogram: true,
+            ..Default::default()
+        };
+        let (_, stats) = build_hdt_with_stats(files, dest, &opts)?;
+        let ex = |name| format!("http://example.org/apple#{name}");
+        let expected = vec![
+            (ex("color"), 1),
+            (ex("isOrganic"), 1),
+            (ex("origin"), 1),
+            (ex("variety"), 1),
+            (ex("weight"), 1),
+            (
+                "http://www.w3.org/1999/02/22-rdf-syntax-ns#type".to_string(),
+                2,
+            ),
+            ("http://www.w3.org/2000/01/rdf-schema#label".to_string(), 2),
+        ];
+        assert_eq!(stats.predicate_histogram, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn build_to_writer() -> Result<(), Rdf2HdtError> {
+        let mut buf = Vec::new();
+        build_hdt_to_writer(
+            vec!["tests/resources/apple.ttl".to_string()],
+            &mut buf,
+            &Options::default(),
+        )?;
+        assert!(buf.starts_with(b"$HDT"));
+
+        // a buffer smaller than the header, held back to be rewritten
+        let opts = Options {
+            write_buffer_size: Some(16),
+            header_metadata: HashMap::from([(
+                "http://purl.org/dc/terms/issued".to_string(),
+                "2025-01-01T00:00:00Z".to_string(),
+            )]),
+            ..Default::default()
+        };
+        let mut small = Vec::new();
+        build_hdt_to_writer(
+            vec!["tests/resources/apple.ttl".to_string()],
+            &mut small,
+            &opts,
+        )?;
+        let opts = Options {
+            write_buffer_size: None,
+            ..opts
+        };
+        let mut default = Vec::new();
+        build_hdt_to_writer(
+            vec!["tests/resources/apple.ttl".to_string()],
+            &mut default,
+            &opts,
+        )?;
+        assert_eq!(small, default);
+        Ok(())
+    }
+
+    #[test]
+    fn input_order_independent() -> Result<(), Rdf2HdtError> {
+        let opts = Options {
+            canonical_bnodes: true,
+            // the creation time would differ between the two builds
+            header_metadata: HashMap::from([(
+                "http://purl.org/dc/terms/issued".to_string(),
+                "2025-01-01T00:00:00Z".to_string(),
+            )]),
+            ..Default::default()
+        };
+        let files = [
+            "tests/resources/bnodes_a.ttl",
+            "tests/resources/apple.ttl",
+            "tests/resources/duplicates.ttl",
+        ];
+        let mut outputs = Vec::new();
+        for order in [[0, 1, 2], [2, 0, 1], [1, 2, 0]] {
+            let mut buf = Vec::new();
+            build_hdt_to_writer(
+                order.iter().map(|i| files[*i].to_string()).collect(),
+                &mut buf,
+                &opts,
+            )?;
+            outputs.push(buf);
+        }
+        assert_eq!(outputs[0], outputs[1]);
+        assert_eq!(outputs[0], outputs[2]);
+        Ok(())
+    }
+
+    #[test]
+    fn custom_temp_dir() -> Result<(), Rdf2HdtError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let opts = Options {
+            temp_dir: Some(tmp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &opts,
+        )?;
+
+        let opts = Options {
+            temp_dir: Some(tmp_dir.path().join("missing")),
+            ..Default::default()
+        };
+        assert!(
+            build_hdt(
+                vec!["tests/resources/apple.ttl".to_string()],
+                tmp_hdt.path().to_str().unwrap(),
+                &opts,
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn intermediate_cleanup() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        for keep_intermediate in [false, true] {
+            let tmp_dir = tempfile::tempdir()?;
+            let opts = Options {
+                temp_dir: Some(tmp_dir.path().to_path_buf()),
+                keep_intermediate,
+                ..Default::default()
+            };
+            build_hdt(
+                vec!["tests/resources/apple.ttl".to_string()],
+                tmp_hdt.path().to_str().unwrap(),
+                &opts,
+            )?;
+            let left = std::fs::read_dir(tmp_dir.path())?.count();
+            assert_eq!(left, usize::from(keep_intermediate));
+        }
+
+        // failed conversions clean up as well
+        let tmp_dir = tempfile::tempdir()?;
+        let opts = Options {
+            temp_dir: Some(tmp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        assert!(
+            build_hdt(
+                vec!["tests/resources/missing.ttl".to_string()],
+                tmp_hdt.path().to_str().unwrap(),
+                &opts,
+            )
+            .is_err()
+        );
+        assert_eq!(std::fs::read_dir(tmp_dir.path())?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn cancelled_build() -> Result<(), Rdf2HdtError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let opts = Options {
+            temp_dir: Some(tmp_dir.path().to_path_buf()),
+            cancel: Some(cancel),
+            // cancel from a progress callback, as another thread would
+            progress: Some(Box::new(move |_, count| {
+                if count > 0 {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            })),
+            ..Default::default()
+        };
+        let dest = tmp_dir.path().join("cancelled.hdt");
+        let res = build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            dest.to_str().unwrap(),
+            &opts,
+        );
+        assert!(matches!(res, Err(Rdf2HdtError::Cancelled)));
+        assert!(!dest.exists());
+        assert_eq!(std::fs::read_dir(tmp_dir.path())?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn stdin_combined_with_files() {
+        let res = build_hdt(
+            vec![
+                STDIN_PATH.to_string(),
+                "tests/resources/apple.ttl".to_string(),
+            ],
+            "tests/resources/generated/stdin.hdt",
+            &Options {
+                input_format: Some(RdfFormat::NTriples),
+                ..Default::default()
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn unsupported_dictionary_format() {
+        let opts = Options {
+            dictionary_format: DictFormat::MultipleSections,
+            ..Default::default()
+        };
+        let res = build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            "tests/resources/generated/mult.hdt",
+            &opts,
+        );
+        assert!(matches!(res, Err(Rdf2HdtError::UnsupportedFormat(_))));
+    }
+
+    #[test]
+    fn stdin_without_format() {
+        let res = build_hdt(
+            vec![STDIN_PATH.to_string()],
+            "tests/resources/generated/stdin.hdt",
+            &Options::default(),
+        );
+        assert!(res.is_err());
+    }
+
     fn find_ttl_files<P: AsRef<std::path::Path>>(dir: P) -> Vec<String> {
         WalkDir::new(dir)
             .into_iter()
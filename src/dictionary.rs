@@ -0,0 +1,367 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use crate::error::Rdf2HdtError;
+use crate::vocab::compact_iri_with;
+use hdt::four_sect_dict::IdKind;
+use std::{fmt, io::Write};
+
+/// Sections of a four-section HDT dictionary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Terms used both as subject and object.
+    Shared,
+    /// Terms only used as subject.
+    Subjects,
+    /// Terms used as predicate.
+    Predicates,
+    /// Terms only used as object.
+    Objects,
+}
+
+impl Section {
+    /// All sections, in the order they are stored in.
+    pub const ALL: [Section; 4] = [
+        Section::Shared,
+        Section::Subjects,
+        Section::Predicates,
+        Section::Objects,
+    ];
+
+    /// Dictionary IDs of the section's terms.
+    ///
+    /// Shared terms come first in both the subject and object ID ranges, and
+    /// predicates have ID ranges of their own.
+    pub fn ids(self, hdt: &hdt::Hdt) -> std::ops::RangeInclusive<usize> {
+        let dict = &hdt.dict;
+        let shared = dict.shared.num_strings;
+        match self {
+            Section::Shared => 1..=shared,
+            Section::Subjects => shared + 1..=shared + dict.subjects.num_strings,
+            Section::Predicates => 1..=dict.predicates.num_strings,
+            Section::Objects => shared + 1..=shared + dict.objects.num_strings,
+        }
+    }
+
+    fn id_kind(self) -> IdKind {
+        match self {
+            Section::Shared | Section::Subjects => IdKind::Subject,
+            Section::Predicates => IdKind::Predicate,
+            Section::Objects => IdKind::Object,
+        }
+    }
+}
+
+/// Position of a term in a triple, which determines its dictionary ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermRole {
+    /// Subject IDs number the shared terms, then the subjects.
+    Subject,
+    /// Predicate IDs number the predicates.
+    Predicate,
+    /// Object IDs number the shared terms, then the objects.
+    Object,
+}
+
+impl TermRole {
+    fn id_kind(self) -> IdKind {
+        match self {
+            TermRole::Subject => IdKind::Subject,
+            TermRole::Predicate => IdKind::Predicate,
+            TermRole::Object => IdKind::Object,
+        }
+    }
+
+    /// Highest ID in the role, IDs start at 1.
+    fn max_id(self, hdt: &hdt::Hdt) -> usize {
+        let dict = &hdt.dict;
+        match self {
+            TermRole::Subject => dict.shared.num_strings + dict.subjects.num_strings,
+            TermRole::Predicate => dict.predicates.num_strings,
+            TermRole::Object => dict.shared.num_strings + dict.objects.num_strings,
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Section::Shared => "shared",
+            Section::Subjects => "subjects",
+            Section::Predicates => "predicates",
+            Section::Objects => "objects",
+        })
+    }
+}
+
+/// Writes every dictionary entry as a `section`, `id`, `term` tab-separated line.
+///
+/// Sections are written in [`Section::ALL`] order and terms in ID order, so the
+/// dumps of two builds can be diffed. Backslashes, tabs and line breaks in terms
+/// are escaped as `\\`, `\t`, `\n` and `\r`.
+pub fn dump_dictionary(hdt: &hdt::Hdt, writer: impl Write) -> Result<(), Rdf2HdtError> {
+    dump(hdt, writer, None)
+}
+
+/// Same as [`dump_dictionary`], with IRI terms shortened to prefixed names by
+/// [`compact_iri_with`] and `prefixes`, for reading rather than processing.
+///
+/// Literals and blank nodes are written unchanged.
+pub fn dump_dictionary_compact(
+    hdt: &hdt::Hdt,
+    writer: impl Write,
+    prefixes: &[(String, String)],
+) -> Result<(), Rdf2HdtError> {
+    dump(hdt, writer, Some(prefixes))
+}
+
+fn dump(
+    hdt: &hdt::Hdt,
+    mut writer: impl Write,
+    prefixes: Option<&[(String, String)]>,
+) -> Result<(), Rdf2HdtError> {
+    for section in Section::ALL {
+        for id in section.ids(hdt) {
+            let mut term = read_term(hdt, section, id)?;
+            let iri = !term.starts_with('"') && !term.starts_with("_:");
+            if let Some(prefixes) = prefixes.filter(|_| iri) {
+                term = compact_iri_with(&term, prefixes);
+            }
+            writeln!(writer, "{section}\t{id}\t{}", escape(&term))?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_term(hdt: &hdt::Hdt, section: Section, id: usize) -> Result<String, Rdf2HdtError> {
+    hdt.dict
+        .id_to_string(id, section.id_kind())
+        .map_err(|e| Rdf2HdtError::InvalidInput(format!("cannot read {section} term {id}: {e}")))
+}
+
+/// Iterator over every dictionary entry of an HDT it owns, as its section and
+/// term.
+///
+/// Sections come in [`Section::ALL`] order and terms in ID order, the order
+/// [`dump_dictionary`] writes them in. Within a section, the terms are sorted
+/// and without duplicates, and the `n`th term of a section has the `n`th ID of
+/// [`Section::ids`].
+pub struct DictionaryTerms {
+    hdt: hdt::Hdt,
+    sections: std::array::IntoIter<Section, 4>,
+    current: Option<(Section, std::ops::RangeInclusive<usize>)>,
+}
+
+impl DictionaryTerms {
+    /// Iterates over the dictionary of `hdt`.
+    pub fn new(hdt: hdt::Hdt) -> Self {
+        Self {
+            hdt,
+            sections: Section::ALL.into_iter(),
+            current: None,
+        }
+    }
+
+    /// The HDT whose dictionary is iterated over.
+    pub fn hdt(&self) -> &hdt::Hdt {
+        &self.hdt
+    }
+}
+
+impl Iterator for DictionaryTerms {
+    type Item = Result<(Section, String), Rdf2HdtError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next = self
+                .current
+                .as_mut()
+                .and_then(|(s, ids)| Some((*s, ids.next()?)));
+            if let Some((section, id)) = next {
+                return Some(read_term(&self.hdt, section, id).map(|term| (section, term)));
+            }
+            let section = self.sections.next()?;
+            self.current = Some((section, section.ids(&self.hdt)));
+        }
+    }
+}
+
+/// Returns the ID of `term` in `role`, or `None` if no triple uses it that way.
+///
+/// A shared term has the same ID as subject and object. Terms are written the
+/// way the hdt crate stores them, see [`term_sections`].
+pub fn id_for_term(hdt: &hdt::Hdt, term: &str, role: TermRole) -> Option<usize> {
+    let id = hdt.dict.string_to_id(term, role.id_kind());
+    (id > 0).then_some(id)
+}
+
+/// Returns the term with ID `id` in `role`, the inverse of [`id_for_term`].
+pub fn term_for_id(hdt: &hdt::Hdt, id: usize, role: TermRole) -> Option<String> {
+    (1..=role.max_id(hdt))
+        .contains(&id)
+        .then(|| hdt.dict.id_to_string(id, role.id_kind()).ok())
+        .flatten()
+}
+
+/// Returns the sections holding `term`, in [`Section::ALL`] order, or no section
+/// if the HDT doesn't use it.
+///
+/// A term used both as subject and object is only stored once, in
+/// [`Section::Shared`]. Terms are written the way the hdt crate stores them: IRIs
+/// without angle brackets, literals with their quotes.
+pub fn term_sections(hdt: &hdt::Hdt, term: &str) -> Vec<Section> {
+    let dict = &hdt.dict;
+    let shared = dict.shared.num_strings;
+    let mut sections = Vec::new();
+    let subject = dict.string_to_id(term, IdKind::Subject);
+    let object = dict.string_to_id(term, IdKind::Object);
+    if (1..=shared).contains(&subject) || (1..=shared).contains(&object) {
+        sections.push(Section::Shared);
+    }
+    if subject > shared {
+        sections.push(Section::Subjects);
+    }
+    if dict.string_to_id(term, IdKind::Predicate) > 0 {
+        sections.push(Section::Predicates);
+    }
+    if object > shared {
+        sections.push(Section::Objects);
+    }
+    sections
+}
+
+fn escape(term: &str) -> String {
+    let mut escaped = String::with_capacity(term.len());
+    for c in term.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{Options, build_hdt};
+
+    #[test]
+    fn dump_apple() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        let mut dump = Vec::new();
+        dump_dictionary(&hdt, &mut dump)?;
+        let dump = String::from_utf8(dump).unwrap();
+        let lines: Vec<_> = dump.lines().collect();
+        // 1 shared, 1 subject, 7 predicates and 8 objects
+        assert_eq!(lines.len(), 17);
+        assert_eq!(lines[0], "shared\t1\thttp://example.org/apple#Fruit");
+        assert!(lines.contains(&"subjects\t2\thttp://example.org/apple#Apple"));
+        assert!(lines.iter().all(|l| l.split('\t').count() == 3));
+
+        let mut dump = Vec::new();
+        let prefixes = [("ex".to_string(), "http://example.org/apple#".to_string())];
+        dump_dictionary_compact(&hdt, &mut dump, &prefixes)?;
+        let dump = String::from_utf8(dump).unwrap();
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines[0], "shared\t1\tex:Fruit");
+        assert!(lines.iter().any(|l| l.ends_with("\trdfs:label")));
+        assert!(lines.iter().any(|l| l.ends_with("\t\"Fruit\"")));
+        Ok(())
+    }
+
+    #[test]
+    fn shared_terms() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt(
+            vec!["tests/resources/shared.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        let ex = |name| format!("http://example.org/shared#{name}");
+        // ex:b and ex:c are objects of one triple and subjects of another
+        assert_eq!(term_sections(&hdt, &ex("b")), [Section::Shared]);
+        assert_eq!(term_sections(&hdt, &ex("c")), [Section::Shared]);
+        assert_eq!(term_sections(&hdt, &ex("a")), [Section::Subjects]);
+        assert_eq!(term_sections(&hdt, &ex("knows")), [Section::Predicates]);
+        assert_eq!(term_sections(&hdt, "\"c\""), [Section::Objects]);
+        assert!(term_sections(&hdt, &ex("missing")).is_empty());
+        assert_eq!(Section::Shared.ids(&hdt).count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn term_ids() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt(
+            vec!["tests/resources/shared.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        let b = "http://example.org/shared#b";
+        // shared terms come first, with one ID for both roles
+        let id = id_for_term(&hdt, b, TermRole::Subject).unwrap();
+        assert!(Section::Shared.ids(&hdt).contains(&id));
+        assert_eq!(id_for_term(&hdt, b, TermRole::Object), Some(id));
+        assert_eq!(id_for_term(&hdt, b, TermRole::Predicate), None);
+        assert_eq!(term_for_id(&hdt, id, TermRole::Object).as_deref(), Some(b));
+
+        let a = "http://example.org/shared#a";
+        let id = id_for_term(&hdt, a, TermRole::Subject).unwrap();
+        assert!(Section::Subjects.ids(&hdt).contains(&id));
+        assert_eq!(id_for_term(&hdt, a, TermRole::Object), None);
+        assert_eq!(term_for_id(&hdt, id, TermRole::Subject).as_deref(), Some(a));
+        assert_eq!(term_for_id(&hdt, 0, TermRole::Subject), None);
+        assert_eq!(term_for_id(&hdt, 100, TermRole::Predicate), None);
+        Ok(())
+    }
+
+    #[test]
+    fn long_terms() -> Result<(), Rdf2HdtError> {
+        // multi-kilobyte terms, two of them differing only in their last byte so
+        // front coding shares almost all of it
+        let blob = "QUJD".repeat(2048);
+        let json = "{\"key\": \"value\\n\"}, ".repeat(512);
+        let subject = format!("http://example.org/long#{}", "s".repeat(4096));
+        let nt = format!(
+            "<{subject}> <http://example.org/long#p> \"{blob}A\" .\n\
+             <{subject}> <http://example.org/long#p> \"{blob}B\" .\n\
+             <{subject}> <http://example.org/long#p> \"{}\" .\n",
+            json.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        let tmp_nt = tempfile::Builder::new().suffix(".nt").tempfile()?;
+        std::fs::write(tmp_nt.path(), nt)?;
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let hdt = build_hdt(
+            vec![tmp_nt.path().to_str().unwrap().to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        assert_eq!(Section::Objects.ids(&hdt).count(), 3);
+        for (term, role) in [
+            (subject, TermRole::Subject),
+            (format!("\"{blob}A\""), TermRole::Object),
+            (format!("\"{blob}B\""), TermRole::Object),
+            (format!("\"{json}\""), TermRole::Object),
+        ] {
+            let id = id_for_term(&hdt, &term, role).unwrap();
+            assert_eq!(term_for_id(&hdt, id, role), Some(term));
+        }
+        assert_eq!(crate::integrity::verify_section_crcs(tmp_hdt.path())?, []);
+        Ok(())
+    }
+
+    #[test]
+    fn escape_terms() {
+        assert_eq!(escape("\"a\tb\nc\\\\d\""), "\"a\\tb\\nc\\\\\\\\d\"");
+    }
+}
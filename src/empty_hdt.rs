@@ -0,0 +1,110 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use crate::header::ControlInfo;
+use crate::integrity::{
+    BITMAP, CRC8, CRC32C, DICTIONARY, DICTIONARY_FOUR, GLOBAL, HEADER, LOG_SEQUENCE, PFC_SECTION,
+    TRIPLES, TRIPLES_BITMAP,
+};
+
+/// Block size written in the preamble of the empty dictionary sections, the one
+/// used by the hdt crate and the C++ HDT library.
+const PFC_BLOCK_SIZE: u64 = 16;
+
+/// Returns an HDT without triples: an empty header, four empty dictionary
+/// sections and empty triples bitmaps and sequences, all with valid checksums.
+///
+/// The hdt crate can't build one from an empty N-Triples file, so the sections
+/// are written as the hdt crate would write them for zero terms. The header
+/// statistics are added while writing, like for any other HDT.
+pub(crate) fn empty_hdt() -> Vec<u8> {
+    let mut out = Vec::new();
+    let format = |iri: &str| format!("<{iri}>");
+    ControlInfo {
+        kind: GLOBAL,
+        format: b"<http://purl.org/HDT/hdt#HDTv1>",
+        properties: "",
+        end: 0,
+    }
+    .write(&mut out, 0);
+    ControlInfo {
+        kind: HEADER,
+        format: b"ntriples",
+        properties: "length=0;",
+        end: 0,
+    }
+    .write(&mut out, 0);
+
+    ControlInfo {
+        kind: DICTIONARY,
+        format: format(DICTIONARY_FOUR).as_bytes(),
+        properties: "mapping=1;sizeStrings=0;",
+        end: 0,
+    }
+    .write(&mut out, 0);
+    for _ in 0..4 {
+        preamble(&mut out, &[PFC_SECTION], &[0, 0, PFC_BLOCK_SIZE]);
+        // a single block offset, the end of the empty string data
+        preamble(&mut out, &[LOG_SEQUENCE, 1], &[1]);
+        data(&mut out, &[0]);
+        data(&mut out, &[]);
+    }
+
+    ControlInfo {
+        kind: TRIPLES,
+        format: format(TRIPLES_BITMAP).as_bytes(),
+        properties: "order=1;",
+        end: 0,
+    }
+    .write(&mut out, 0);
+    for _ in 0..2 {
+        preamble(&mut out, &[BITMAP], &[0]);
+        data(&mut out, &[]);
+    }
+    for _ in 0..2 {
+        preamble(&mut out, &[LOG_SEQUENCE, 1], &[0]);
+        data(&mut out, &[]);
+    }
+    out
+}
+
+/// Writes a section preamble: `bytes`, then `values` as variable-length
+/// integers, then a CRC8 of both.
+fn preamble(out: &mut Vec<u8>, bytes: &[u8], values: &[u64]) {
+    let start = out.len();
+    out.extend_from_slice(bytes);
+    for value in values {
+        let mut value = *value;
+        while value >= 0x80 {
+            out.push((value & 0x7f) as u8);
+            value >>= 7;
+        }
+        // the last byte has its high bit set
+        out.push(value as u8 | 0x80);
+    }
+    let crc = CRC8.checksum(&out[start..]);
+    out.push(crc);
+}
+
+/// Writes section data followed by its CRC32C.
+fn data(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(data);
+    out.extend_from_slice(&CRC32C.checksum(data).to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Rdf2HdtError;
+    use crate::integrity::verify_section_crcs;
+
+    #[test]
+    fn empty_hdt_sections() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        std::fs::write(tmp_hdt.path(), empty_hdt())?;
+        assert_eq!(verify_section_crcs(tmp_hdt.path())?, []);
+        let hdt = hdt::Hdt::read(std::io::Cursor::new(empty_hdt()))?;
+        assert_eq!(hdt.triples_with_pattern(None, None, None).count(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use oxrdfio::{RdfParseError, RdfSyntaxError};
+use std::fmt;
+
+/// Errors returned while converting RDF to HDT.
+#[derive(Debug)]
+pub enum Rdf2HdtError {
+    /// Reading the input or writing the output failed.
+    Io(std::io::Error),
+    /// The RDF input is malformed.
+    ///
+    /// `line` and `column` are 1-based, or 0 if the parser did not report a
+    /// position. The column is counted in characters when the parser reports
+    /// it, or in bytes for invalid UTF-8.
+    Parse {
+        line: usize,
+        column: usize,
+        msg: String,
+    },
+    /// The input format can't be determined or isn't supported.
+    UnsupportedFormat(String),
+    /// The conversion was called with invalid arguments.
+    InvalidInput(String),
+    /// The hdt crate failed to build or write the HDT.
+    Hdt(hdt::hdt::Error),
+    /// The input has no triples, see [`Options::allow_empty`](crate::builder::Options::allow_empty).
+    EmptyInput,
+    /// The conversion was aborted through [`Options::cancel`](crate::builder::Options::cancel).
+    Cancelled,
+    /// The input goes over a limit set in the options, such as
+    /// [`Options::max_distinct_terms`](crate::builder::Options::max_distinct_terms).
+    LimitExceeded(String),
+    /// The HDT read back from the output differs from the one that was built.
+    Verification(String),
+}
+
+impl fmt::Display for Rdf2HdtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Parse {
+                line,
+                column: 0,
+                msg,
+            } => write!(f, "parse error on line {line}: {msg}"),
+            Self::Parse { line, column, msg } => {
+                write!(f, "parse error on line {line}, column {column}: {msg}")
+            }
+            Self::UnsupportedFormat(msg) => write!(f, "unsupported format: {msg}"),
+            Self::InvalidInput(msg) => write!(f, "invalid input: {msg}"),
+            Self::Hdt(e) => write!(f, "HDT error: {e}"),
+            Self::Verification(msg) => write!(f, "verification failed: {msg}"),
+            Self::EmptyInput => write!(f, "the input has no triples"),
+            Self::Cancelled => write!(f, "the conversion was cancelled"),
+            Self::LimitExceeded(msg) => write!(f, "limit exceeded: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Rdf2HdtError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Hdt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Rdf2HdtError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<hdt::hdt::Error> for Rdf2HdtError {
+    fn from(e: hdt::hdt::Error) -> Self {
+        Self::Hdt(e)
+    }
+}
+
+impl From<RdfParseError> for Rdf2HdtError {
+    fn from(e: RdfParseError) -> Self {
+        match e {
+            RdfParseError::Io(e) => Self::Io(e),
+            RdfParseError::Syntax(e) => {
+                let (line, column) = position(&e);
+                Self::Parse {
+                    line,
+                    column,
+                    msg: e.to_string(),
+                }
+            }
+        }
+    }
+}
+
+/// The 1-based line and column where `e` starts, or `(0, 0)` if the parser did
+/// not report it.
+pub(crate) fn position(e: &RdfSyntaxError) -> (usize, usize) {
+    e.location().map_or((0, 0), |location| {
+        (
+            location.start.line as usize + 1,
+            location.start.column as usize + 1,
+        )
+    })
+}
@@ -0,0 +1,459 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use crate::builder::{BuildStats, Options};
+use crate::error::Rdf2HdtError;
+use oxrdf::{BlankNode, Literal, NamedNode, NamedOrBlankNode, Term, Triple, vocab::rdf};
+use oxrdfio::{RdfFormat, RdfParser, RdfSerializer};
+use std::io::{self, Write};
+
+const CONTROL_INFO_COOKIE: &[u8; 4] = b"$HDT";
+const HDT_DATASET: &str = "http://purl.org/HDT/hdt#Dataset";
+const HDT_ORIGINAL_SIZE: &str = "http://purl.org/HDT/hdt#originalSize";
+const VOID_TRIPLES: &str = "http://rdfs.org/ns/void#triples";
+const VOID_PROPERTIES: &str = "http://rdfs.org/ns/void#properties";
+const VOID_DISTINCT_SUBJECTS: &str = "http://rdfs.org/ns/void#distinctSubjects";
+const VOID_DISTINCT_OBJECTS: &str = "http://rdfs.org/ns/void#distinctObjects";
+const DCTERMS_ISSUED: &str = "http://purl.org/dc/terms/issued";
+const PROV_WAS_GENERATED_BY: &str = "http://www.w3.org/ns/prov#wasGeneratedBy";
+
+/// Properties added to the header of the HDT written by the hdt crate.
+pub(crate) struct HeaderProperties {
+    /// Added unless the header already has a value for the property.
+    defaults: Vec<(NamedNode, Term)>,
+    /// From [`Options::header_metadata`], replacing any value already in the header.
+    extra: Vec<(NamedNode, Term)>,
+}
+
+impl HeaderProperties {
+    pub(crate) fn new(stats: &BuildStats, opts: &Options) -> Result<Self, Rdf2HdtError> {
+        let count = |n: u64| Term::from(Literal::new_simple_literal(n.to_string()));
+        let mut defaults = vec![
+            (VOID_TRIPLES, count(stats.triples)),
+            (VOID_PROPERTIES, count(stats.predicates)),
+            (VOID_DISTINCT_SUBJECTS, count(stats.subjects)),
+            (VOID_DISTINCT_OBJECTS, count(stats.objects)),
+        ];
+        if stats.input_bytes > 0 {
+            defaults.push((HDT_ORIGINAL_SIZE, count(stats.input_bytes)));
+        }
+        defaults.push((
+            DCTERMS_ISSUED,
+            Literal::new_simple_literal(utc_timestamp(build_time())).into(),
+        ));
+        defaults.push((
+            PROV_WAS_GENERATED_BY,
+            Literal::new_simple_literal(concat!("rdf2hdt ", env!("CARGO_PKG_VERSION"))).into(),
+        ));
+
+        let mut extra = opts
+            .header_metadata
+            .iter()
+            .map(|(property, value)| {
+                let property = NamedNode::new(property.as_str()).map_err(|e| {
+                    Rdf2HdtError::InvalidInput(format!("invalid header property {property}: {e}"))
+                })?;
+                Ok((property, Literal::new_simple_literal(value.as_str()).into()))
+            })
+            .collect::<Result<Vec<_>, Rdf2HdtError>>()?;
+        // HashMap order varies between runs
+        extra.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        Ok(Self {
+            defaults: defaults
+                .into_iter()
+                .map(|(property, value)| (NamedNode::new_unchecked(property), value))
+                .collect(),
+            extra,
+        })
+    }
+
+    /// Returns the N-Triples header `body` with the properties added.
+    fn apply(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+        let mut triples = RdfParser::from_format(RdfFormat::NTriples)
+            .for_slice(body)
+            .map(|q| q.map(|q| Triple::new(q.subject, q.predicate, q.object)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let dataset = triples
+            .iter()
+            .find(|t| {
+                t.predicate == rdf::TYPE
+                    && t.object == Term::from(NamedNode::new_unchecked(HDT_DATASET))
+            })
+            .map(|t| t.subject.clone());
+        let dataset = match dataset {
+            Some(dataset) => dataset,
+            None => {
+                let dataset = NamedOrBlankNode::from(BlankNode::new_unchecked("dataset"));
+                triples.push(Triple::new(
+                    dataset.clone(),
+                    rdf::TYPE,
+                    NamedNode::new_unchecked(HDT_DATASET),
+                ));
+                dataset
+            }
+        };
+        for (property, value) in &self.defaults {
+            if !triples.iter().any(|t| t.predicate == *property) {
+                triples.push(Triple::new(
+                    dataset.clone(),
+                    property.clone(),
+                    value.clone(),
+                ));
+            }
+        }
+        for (property, value) in &self.extra {
+            triples.retain(|t| t.subject != dataset || t.predicate != *property);
+            triples.push(Triple::new(
+                dataset.clone(),
+                property.clone(),
+                value.clone(),
+            ));
+        }
+
+        let mut serializer = RdfSerializer::from_format(RdfFormat::NTriples).for_writer(Vec::new());
+        for triple in &triples {
+            serializer.serialize_triple(triple)?;
+        }
+        serializer.finish()
+    }
+}
+
+/// Adds [`HeaderProperties`] to an HDT while it is being written.
+///
+/// Bytes are held back until the global and header control information and the
+/// header itself have been written, then the header is rewritten, with its
+/// length and checksum updated, and everything after it is passed through.
+pub(crate) struct HeaderWriter<W: Write> {
+    inner: W,
+    /// `None` once the header has been rewritten.
+    buffer: Option<Vec<u8>>,
+    properties: HeaderProperties,
+}
+
+impl<W: Write> HeaderWriter<W> {
+    pub(crate) fn new(inner: W, properties: HeaderProperties) -> Self {
+        Self {
+            inner,
+            buffer: Some(Vec::new()),
+            properties,
+        }
+    }
+
+    /// Returns the inner writer, after writing any bytes still held back.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        if let Some(buffer) = self.buffer.take() {
+            // the output ended before the header, leave it as is
+            self.inner.write_all(&buffer)?;
+        }
+        Ok(self.inner)
+    }
+}
+
+/// Returns `buffer` with the header rewritten, or `None` if it doesn't hold the
+/// whole header yet.
+fn rewrite(properties: &HeaderProperties, buffer: &[u8]) -> io::Result<Option<Vec<u8>>> {
+    let Some(global) = ControlInfo::read(buffer, 0)? else {
+        return Ok(None);
+    };
+    let Some(header) = ControlInfo::read(buffer, global.end)? else {
+        return Ok(None);
+    };
+    let length = header
+        .property("length")
+        .and_then(|l| l.parse::<usize>().ok());
+    let Some(length) = length else {
+        return Err(invalid_data("HDT header without a valid length"));
+    };
+    let body_end = header.end + length;
+    if buffer.len() < body_end {
+        return Ok(None);
+    }
+
+    let body = properties.apply(&buffer[header.end..body_end])?;
+    let mut rewritten = buffer[..global.end].to_vec();
+    header.write(&mut rewritten, body.len());
+    rewritten.extend_from_slice(&body);
+    rewritten.extend_from_slice(&buffer[body_end..]);
+    Ok(Some(rewritten))
+}
+
+impl<W: Write> Write for HeaderWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(buffer) = &mut self.buffer else {
+            return self.inner.write(buf);
+        };
+        buffer.extend_from_slice(buf);
+        if let Some(rewritten) = rewrite(&self.properties, buffer)? {
+            self.inner.write_all(&rewritten)?;
+            self.buffer = None;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Control information preceding each HDT section: the `$HDT` cookie, a type
+/// byte, then a format IRI and `key=value;` properties, both NUL terminated,
+/// and a CRC16 of all that.
+pub(crate) struct ControlInfo<'a> {
+    pub(crate) kind: u8,
+    pub(crate) format: &'a [u8],
+    pub(crate) properties: &'a str,
+    /// Offset following the CRC.
+    pub(crate) end: usize,
+}
+
+impl<'a> ControlInfo<'a> {
+    /// Reads the control information at `start`, or returns `None` if `buffer`
+    /// doesn't hold all of it yet.
+    pub(crate) fn read(buffer: &'a [u8], start: usize) -> io::Result<Option<Self>> {
+        let Some(head) = buffer.get(start..start + 5) else {
+            return Ok(None);
+        };
+        if &head[..4] != CONTROL_INFO_COOKIE {
+            return Err(invalid_data("missing HDT control information"));
+        }
+        let format_start = start + 5;
+        let Some(format_len) = buffer[format_start..].iter().position(|b| *b == 0) else {
+            return Ok(None);
+        };
+        let properties_start = format_start + format_len + 1;
+        let Some(properties_len) = buffer[properties_start..].iter().position(|b| *b == 0) else {
+            return Ok(None);
+        };
+        let end = properties_start + properties_len + 3;
+        if buffer.len() < end {
+            return Ok(None);
+        }
+        let properties = std::str::from_utf8(&buffer[properties_start..end - 3])
+            .map_err(|_| invalid_data("HDT control information properties are not UTF-8"))?;
+        Ok(Some(Self {
+            kind: head[4],
+            format: &buffer[format_start..format_start + format_len],
+            properties,
+            end,
+        }))
+    }
+
+    pub(crate) fn property(&self, key: &str) -> Option<&'a str> {
+        self.properties
+            .split(';')
+            .find_map(|p| p.strip_prefix(key)?.strip_prefix('='))
+    }
+
+    /// Whether the CRC16 stored in `buffer` matches the control information read
+    /// from `start`.
+    pub(crate) fn crc_matches(&self, buffer: &[u8], start: usize) -> bool {
+        let crc = crc::Crc::<u16>::new(&crc::CRC_16_ARC).checksum(&buffer[start..self.end - 2]);
+        buffer[self.end - 2..self.end] == crc.to_le_bytes()
+    }
+
+    /// Writes the control information with `length` as the section length.
+    pub(crate) fn write(&self, out: &mut Vec<u8>, length: usize) {
+        let start = out.len();
+        out.extend_from_slice(CONTROL_INFO_COOKIE);
+        out.push(self.kind);
+        out.extend_from_slice(self.format);
+        out.push(0);
+        for property in self.properties.split(';').filter(|p| !p.is_empty()) {
+            if property.starts_with("length=") {
+                out.extend_from_slice(format!("length={length};").as_bytes());
+            } else {
+                out.extend_from_slice(property.as_bytes());
+                out.push(b';');
+            }
+        }
+        out.push(0);
+        let crc = crc::Crc::<u16>::new(&crc::CRC_16_ARC).checksum(&out[start..]);
+        out.extend_from_slice(&crc.to_le_bytes());
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// The time recorded as the creation time of the output: that given by the
+/// `SOURCE_DATE_EPOCH` environment variable when set, for reproducible builds,
+/// or the current time.
+pub(crate) fn build_time() -> std::time::SystemTime {
+    let epoch = std::env::var("SOURCE_DATE_EPOCH").ok();
+    source_date(epoch.as_deref()).unwrap_or_else(std::time::SystemTime::now)
+}
+
+/// Parses a `SOURCE_DATE_EPOCH` value, in seconds since the Unix epoch.
+fn source_date(epoch: Option<&str>) -> Option<std::time::SystemTime> {
+    let epoch = epoch?;
+    match epoch.trim().parse::<u64>() {
+        Ok(secs) => Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs)),
+        Err(_) => {
+            log::warn!("ignoring invalid SOURCE_DATE_EPOCH {epoch:?}");
+            None
+        }
+    }
+}
+
+/// Formats `time` as an ISO 8601 UTC timestamp, e.g. `2025-01-31T09:05:00Z`.
+pub(crate) fn utc_timestamp(time: std::time::SystemTime) -> String {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+    // civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// Global and header control information with an empty header, followed by `rest`.
+    fn minimal_hdt(rest: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        ControlInfo {
+            kind: 1,
+            format: b"<http://purl.org/HDT/hdt#HDTv1>",
+            properties: "",
+            end: 0,
+        }
+        .write(&mut out, 0);
+        ControlInfo {
+            kind: 2,
+            format: b"ntriples",
+            properties: "length=0;",
+            end: 0,
+        }
+        .write(&mut out, 0);
+        out.extend_from_slice(rest);
+        out
+    }
+
+    fn header_body(hdt: &[u8]) -> String {
+        let global = ControlInfo::read(hdt, 0).unwrap().unwrap();
+        let header = ControlInfo::read(hdt, global.end).unwrap().unwrap();
+        let length: usize = header.property("length").unwrap().parse().unwrap();
+        String::from_utf8(hdt[header.end..header.end + length].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn rewrite_header() -> Result<(), Rdf2HdtError> {
+        let stats = BuildStats {
+            triples: 9,
+            input_bytes: 382,
+            ..Default::default()
+        };
+        let opts = Options {
+            header_metadata: [(
+                "http://purl.org/dc/terms/license".to_string(),
+                "CC0".to_string(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let mut writer = HeaderWriter::new(Vec::new(), HeaderProperties::new(&stats, &opts)?);
+        // written in small chunks, as a buffered writer might
+        for chunk in minimal_hdt(b"dictionary").chunks(3) {
+            writer.write_all(chunk)?;
+        }
+        let out = writer.finish()?;
+
+        let body = header_body(&out);
+        assert!(body.contains("_:dataset <http://rdfs.org/ns/void#triples> \"9\" ."));
+        assert!(body.contains("_:dataset <http://purl.org/HDT/hdt#originalSize> \"382\" ."));
+        assert!(body.contains("_:dataset <http://purl.org/dc/terms/license> \"CC0\" ."));
+        assert!(body.contains(concat!("\"rdf2hdt ", env!("CARGO_PKG_VERSION"), "\"")));
+        assert!(out.ends_with(b"dictionary"));
+        // the rewritten control information is valid, so it reads back
+        assert!(ControlInfo::read(&out, 0)?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn header_of_built_hdt() -> Result<(), Rdf2HdtError> {
+        let opts = Options {
+            header_metadata: [(
+                "http://purl.org/dc/terms/license".to_string(),
+                "CC0".to_string(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        crate::builder::build_hdt_to_writer(
+            vec!["tests/resources/apple.ttl".to_string()],
+            &mut out,
+            &opts,
+        )?;
+        let body = header_body(&out);
+        assert!(body.contains("<http://rdfs.org/ns/void#triples> \"9\" ."));
+        assert!(body.contains("<http://purl.org/dc/terms/license> \"CC0\" ."));
+        // the hdt crate checks the rewritten control information
+        hdt::Hdt::read(std::io::Cursor::new(out))?;
+        Ok(())
+    }
+
+    #[test]
+    fn incomplete_output_is_kept() -> io::Result<()> {
+        let stats = BuildStats::default();
+        let properties = HeaderProperties::new(&stats, &Options::default()).unwrap();
+        let mut writer = HeaderWriter::new(Vec::new(), properties);
+        writer.write_all(b"$HDT\x01<http")?;
+        assert_eq!(writer.finish()?, b"$HDT\x01<http");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_header_property() {
+        let opts = Options {
+            header_metadata: [("not an iri".to_string(), "x".to_string())].into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            HeaderProperties::new(&BuildStats::default(), &opts),
+            Err(Rdf2HdtError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            utc_timestamp(UNIX_EPOCH + Duration::from_secs(1_712_258_645)),
+            "2024-04-04T19:24:05Z"
+        );
+        assert_eq!(
+            utc_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn source_date_epoch() {
+        assert_eq!(
+            source_date(Some("1712258645")),
+            Some(UNIX_EPOCH + Duration::from_secs(1_712_258_645))
+        );
+        assert_eq!(source_date(Some("yesterday")), None);
+        assert_eq!(source_date(None), None);
+    }
+}
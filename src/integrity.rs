@@ -0,0 +1,441 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use crate::dictionary::Section;
+use crate::error::Rdf2HdtError;
+use crate::header::ControlInfo;
+use sha2::{Digest, Sha256};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+pub(crate) const CRC8: crc::Crc<u8> = crc::Crc::<u8>::new(&crc::CRC_8_SMBUS);
+pub(crate) const CRC32C: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+
+pub(crate) const GLOBAL: u8 = 1;
+pub(crate) const HEADER: u8 = 2;
+pub(crate) const DICTIONARY: u8 = 3;
+pub(crate) const TRIPLES: u8 = 4;
+pub(crate) const DICTIONARY_FOUR: &str = "http://purl.org/HDT/hdt#dictionaryFour";
+pub(crate) const TRIPLES_BITMAP: &str = "http://purl.org/HDT/hdt#triplesBitmap";
+pub(crate) const PFC_SECTION: u8 = 2;
+pub(crate) const LOG_SEQUENCE: u8 = 1;
+pub(crate) const BITMAP: u8 = 1;
+
+/// A part of an HDT file whose content doesn't match its stored checksum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrcMismatch {
+    /// Name of the part, e.g. `dictionary shared strings`.
+    pub section: String,
+    /// Offset of the part in the file.
+    pub offset: u64,
+}
+
+/// Reads the HDT file at `path` and recomputes the checksum of every section,
+/// returning the sections whose checksum doesn't match, if any.
+///
+/// Only the section framing is decoded, the dictionary and triples are not
+/// loaded, so this takes little memory whatever the size of the file. Control
+/// information is checked with CRC16, section preambles with CRC8 and section
+/// data with CRC32C, as written by the hdt crate and the C++ HDT library. A file
+/// too malformed to find the sections in is an error.
+pub fn verify_section_crcs(path: impl AsRef<Path>) -> Result<Vec<CrcMismatch>, Rdf2HdtError> {
+    let path = path.as_ref();
+    let mut reader = SectionReader::open(path, false)?;
+    reader.skip_to_triples(path)?;
+    reader.triples()?;
+    Ok(reader.mismatches)
+}
+
+/// The sections of `<http://purl.org/HDT/hdt#triplesBitmap>` triples.
+pub(crate) struct TriplesSections {
+    pub(crate) bitmap_y: Bitmap,
+    pub(crate) bitmap_z: Bitmap,
+    pub(crate) seq_y: LogSequence,
+    pub(crate) seq_z: LogSequence,
+}
+
+/// Reads the triples sections of the HDT file at `path`, failing if a checksum
+/// doesn't match.
+pub(crate) fn read_triples_sections(path: &Path) -> Result<TriplesSections, Rdf2HdtError> {
+    let mut reader = SectionReader::open(path, true)?;
+    reader.skip_to_triples(path)?;
+    let sections = reader.triples()?;
+    if let Some(mismatch) = reader.mismatches.first() {
+        return Err(malformed(
+            path,
+            &format!("the {} checksum doesn't match", mismatch.section),
+        ));
+    }
+    Ok(sections)
+}
+
+/// A sequence of `len` integers of `bits` bits each, packed from the least
+/// significant bit of the first byte.
+pub(crate) struct LogSequence {
+    pub(crate) bits: u8,
+    pub(crate) len: u64,
+    pub(crate) data: Vec<u8>,
+}
+
+impl LogSequence {
+    pub(crate) fn get(&self, index: u64) -> u64 {
+        let mut value = 0;
+        let start = index * u64::from(self.bits);
+        for bit in 0..u64::from(self.bits) {
+            let position = start + bit;
+            let byte = self.data[(position / 8) as usize];
+            value |= u64::from(byte >> (position % 8) & 1) << bit;
+        }
+        value
+    }
+}
+
+/// `len` bits, packed from the least significant bit of the first byte.
+pub(crate) struct Bitmap {
+    pub(crate) len: u64,
+    pub(crate) data: Vec<u8>,
+}
+
+impl Bitmap {
+    pub(crate) fn get(&self, index: u64) -> bool {
+        self.data[(index / 8) as usize] >> (index % 8) & 1 == 1
+    }
+}
+
+fn malformed(path: &Path, msg: &str) -> Rdf2HdtError {
+    Rdf2HdtError::InvalidInput(format!("{} is not a valid HDT file: {msg}", path.display()))
+}
+
+/// Reads the sections of an HDT file in order, keeping track of the offset.
+struct SectionReader<R> {
+    inner: R,
+    offset: u64,
+    mismatches: Vec<CrcMismatch>,
+    /// Keep the data of the triples sections, which is otherwise only checksummed.
+    keep_triples: bool,
+}
+
+impl SectionReader<BufReader<File>> {
+    fn open(path: &Path, keep_triples: bool) -> io::Result<Self> {
+        Ok(Self {
+            inner: BufReader::new(File::open(path)?),
+            offset: 0,
+            mismatches: Vec::new(),
+            keep_triples,
+        })
+    }
+}
+
+impl<R: BufRead> SectionReader<R> {
+    /// Reads the control information, header and dictionary.
+    fn skip_to_triples(&mut self, path: &Path) -> Result<(), Rdf2HdtError> {
+        self.control_info("global", GLOBAL)?;
+        let header = self.control_info("header", HEADER)?;
+        let length = ControlInfo::read(&header, 0)?
+            .and_then(|ci| ci.property("length")?.parse().ok())
+            .ok_or_else(|| malformed(path, "the header has no length"))?;
+        self.skip(length)?;
+
+        let dictionary = self.control_info("dictionary", DICTIONARY)?;
+        self.expect_format(&dictionary, DICTIONARY_FOUR)?;
+        for section in Section::ALL {
+            self.pfc_section(&format!("dictionary {section}"))?;
+        }
+        Ok(())
+    }
+
+    fn triples(&mut self) -> Result<TriplesSections, Rdf2HdtError> {
+        let triples = self.control_info("triples", TRIPLES)?;
+        self.expect_format(&triples, TRIPLES_BITMAP)?;
+        let order = ControlInfo::read(&triples, 0)?.and_then(|ci| ci.property("order"));
+        if self.keep_triples && order != Some("1") {
+            return Err(Rdf2HdtError::UnsupportedFormat(format!(
+                "triples in order {}, only SPO (1) can be decoded",
+                order.unwrap_or("?")
+            )));
+        }
+        Ok(TriplesSections {
+            bitmap_y: self.bitmap("triples Y bitmap")?,
+            bitmap_z: self.bitmap("triples Z bitmap")?,
+            seq_y: self.log_sequence("triples Y sequence", self.keep_triples)?,
+            seq_z: self.log_sequence("triples Z sequence", self.keep_triples)?,
+        })
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_exact(buf)?;
+        self.offset += buf.len() as u64;
+        Ok(())
+    }
+
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.inner).take(len), &mut io::sink())?;
+        self.offset += skipped;
+        if skipped < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn check(&mut self, section: String, offset: u64, matches: bool) {
+        if !matches {
+            self.mismatches.push(CrcMismatch { section, offset });
+        }
+    }
+
+    /// Reads control information of type `kind`, returning its bytes.
+    fn control_info(&mut self, name: &str, kind: u8) -> Result<Vec<u8>, Rdf2HdtError> {
+        let offset = self.offset;
+        let mut raw = vec![0; 5];
+        self.read_exact(&mut raw)?;
+        for _ in 0..2 {
+            self.offset += self.inner.read_until(0, &mut raw)? as u64;
+        }
+        let mut crc = [0; 2];
+        self.read_exact(&mut crc)?;
+        raw.extend_from_slice(&crc);
+
+        let ci = ControlInfo::read(&raw, 0)
+            .ok()
+            .flatten()
+            .filter(|ci| ci.kind == kind)
+            .ok_or_else(|| {
+                Rdf2HdtError::InvalidInput(format!(
+                    "expected the {name} control information at byte {offset}"
+                ))
+            })?;
+        let matches = ci.crc_matches(&raw, 0);
+        self.check(format!("{name} control information"), offset, matches);
+        Ok(raw)
+    }
+
+    fn expect_format(&self, raw: &[u8], format: &str) -> Result<(), Rdf2HdtError> {
+        let Some(ci) = ControlInfo::read(raw, 0)? else {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        };
+        let found = String::from_utf8_lossy(ci.format);
+        if found.trim_start_matches('<').trim_end_matches('>') != format {
+            return Err(Rdf2HdtError::UnsupportedFormat(format!(
+                "cannot check the sections of {found}, only of <{format}>"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reads a variable-length integer, appending its bytes to `preamble`.
+    fn vbyte(&mut self, preamble: &mut Vec<u8>) -> io::Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let mut byte = [0];
+            self.read_exact(&mut byte)?;
+            preamble.push(byte[0]);
+            value |= u64::from(byte[0] & 0x7f) << shift;
+            // the last byte has its high bit set
+            if byte[0] & 0x80 != 0 {
+                return Ok(value);
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "variable-length integer is too long",
+        ))
+    }
+
+    /// Reads a preamble made of a type byte, `bytes` more bytes and `vbytes`
+    /// variable-length integers, and checks its CRC8.
+    fn preamble(
+        &mut self,
+        name: &str,
+        kind: u8,
+        bytes: usize,
+        vbytes: usize,
+    ) -> Result<(Vec<u8>, Vec<u64>), Rdf2HdtError> {
+        let offset = self.offset;
+        let mut preamble = vec![0; 1 + bytes];
+        self.read_exact(&mut preamble)?;
+        if preamble[0] != kind {
+            return Err(Rdf2HdtError::UnsupportedFormat(format!(
+                "{name} at byte {offset} has type {}, expected {kind}",
+                preamble[0]
+            )));
+        }
+        let values = (0..vbytes)
+            .map(|_| self.vbyte(&mut preamble))
+            .collect::<io::Result<_>>()?;
+        let mut crc = [0];
+        self.read_exact(&mut crc)?;
+        self.check(
+            format!("{name} preamble"),
+            offset,
+            CRC8.checksum(&preamble) == crc[0],
+        );
+        Ok((preamble, values))
+    }
+
+    /// Reads `len` bytes of section data, streaming them through the CRC32C
+    /// stored after them, and returns them if `keep` is set.
+    fn data(&mut self, name: &str, len: u64, keep: bool) -> io::Result<Vec<u8>> {
+        let offset = self.offset;
+        let mut digest = CRC32C.digest();
+        let mut kept = Vec::new();
+        let mut remaining = len;
+        while remaining > 0 {
+            let buf = self.inner.fill_buf()?;
+            if buf.is_empty() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let n = buf
+                .len()
+                .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+            digest.update(&buf[..n]);
+            if keep {
+                kept.extend_from_slice(&buf[..n]);
+            }
+            self.inner.consume(n);
+            self.offset += n as u64;
+            remaining -= n as u64;
+        }
+        let mut crc = [0; 4];
+        self.read_exact(&mut crc)?;
+        let matches = digest.finalize() == u32::from_le_bytes(crc);
+        self.check(name.to_string(), offset, matches);
+        Ok(kept)
+    }
+
+    /// A sequence of fixed-width integers: bits per entry and number of entries.
+    fn log_sequence(&mut self, name: &str, keep: bool) -> Result<LogSequence, Rdf2HdtError> {
+        let (preamble, values) = self.preamble(name, LOG_SEQUENCE, 1, 1)?;
+        let bits = preamble[1];
+        let data = self.data(name, (u64::from(bits) * values[0]).div_ceil(8), keep)?;
+        Ok(LogSequence {
+            bits,
+            len: values[0],
+            data,
+        })
+    }
+
+    /// A bitmap: number of bits.
+    fn bitmap(&mut self, name: &str) -> Result<Bitmap, Rdf2HdtError> {
+        let (_, values) = self.preamble(name, BITMAP, 0, 1)?;
+        let data = self.data(name, values[0].div_ceil(8), self.keep_triples)?;
+        Ok(Bitmap {
+            len: values[0],
+            data,
+        })
+    }
+
+    /// A plain front coded dictionary section: number of strings, length of the
+    /// string data and block size, then the block offsets and the strings.
+    fn pfc_section(&mut self, name: &str) -> Result<(), Rdf2HdtError> {
+        let (_, values) = self.preamble(name, PFC_SECTION, 0, 3)?;
+        self.log_sequence(&format!("{name} block offsets"), false)?;
+        self.data(&format!("{name} strings"), values[1], false)?;
+        Ok(())
+    }
+}
+
+/// Passes writes through to an inner writer, computing the SHA-256 of the bytes
+/// written.
+///
+/// Wrap the writer given to
+/// [`build_hdt_to_writer`](crate::builder::build_hdt_to_writer) to checksum the
+/// HDT without reading it back, as [`Options::emit_checksum`](crate::builder::Options::emit_checksum)
+/// does for files.
+pub struct Sha256Writer<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Sha256Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Returns the inner writer and the digest of the bytes written, as
+    /// lowercase hex.
+    pub fn finish(self) -> (W, String) {
+        let digest = self.hasher.finalize();
+        let hex = digest.iter().map(|b| format!("{b:02x}")).collect();
+        (self.inner, hex)
+    }
+}
+
+impl<W: Write> Write for Sha256Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes `digest`, the SHA-256 of `file`, to `file` with a `.sha256` suffix, in
+/// the format of `sha256sum`, and returns the path written.
+///
+/// The file is listed by name only, so `sha256sum -c` checks it when run from
+/// its directory.
+pub(crate) fn write_sha256_file(file: &Path, digest: &str) -> Result<PathBuf, Rdf2HdtError> {
+    let name = file.file_name().unwrap_or(file.as_os_str());
+    let mut sidecar = file.as_os_str().to_os_string();
+    sidecar.push(".sha256");
+    let sidecar = PathBuf::from(sidecar);
+    std::fs::write(
+        &sidecar,
+        format!("{digest}  {}\n", Path::new(name).display()),
+    )?;
+    Ok(sidecar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{Options, build_hdt};
+
+    #[test]
+    fn valid_crcs() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        assert_eq!(verify_section_crcs(tmp_hdt.path())?, []);
+        assert!(verify_section_crcs("tests/resources/apple.hdt")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_strings() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        build_hdt(
+            vec!["tests/resources/apple.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &Options::default(),
+        )?;
+        let mut bytes = std::fs::read(tmp_hdt.path())?;
+        // the only shared term, stored in full at the start of its section
+        let pos = bytes
+            .windows(11)
+            .rposition(|w| w == b"apple#Fruit")
+            .unwrap();
+        bytes[pos + 6] = b'B';
+        std::fs::write(tmp_hdt.path(), &bytes)?;
+
+        let mismatches = verify_section_crcs(tmp_hdt.path())?;
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].section, "dictionary shared strings");
+
+        std::fs::write(tmp_hdt.path(), &bytes[..bytes.len() / 2])?;
+        assert!(verify_section_crcs(tmp_hdt.path()).is_err());
+        Ok(())
+    }
+}
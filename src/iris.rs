@@ -0,0 +1,130 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use crate::builder::Options;
+use oxrdf::{NamedNode, NamedOrBlankNode, Term, Triple};
+
+/// Normalizes the subject, predicate and object IRIs of `triple`, including
+/// those of quoted triples, according to
+/// [`Options::normalize_iris`](crate::builder::Options::normalize_iris) and
+/// [`Options::canonical_trailing_slash`](crate::builder::Options::canonical_trailing_slash).
+pub(crate) fn normalize_iris(triple: &mut Triple, opts: &Options) {
+    if let NamedOrBlankNode::NamedNode(n) = &mut triple.subject {
+        normalize_node(n, opts);
+    }
+    normalize_node(&mut triple.predicate, opts);
+    match &mut triple.object {
+        Term::NamedNode(n) => normalize_node(n, opts),
+        Term::Triple(quoted) => normalize_iris(quoted, opts),
+        _ => {}
+    }
+}
+
+fn normalize_node(node: &mut NamedNode, opts: &Options) {
+    let mut iri = None;
+    if opts.normalize_iris {
+        iri = normalize_iri(node.as_str());
+    }
+    if opts.canonical_trailing_slash {
+        let current = iri.as_deref().unwrap_or(node.as_str());
+        if let Some(trimmed) = trim_trailing_slash(current) {
+            iri = Some(trimmed.to_string());
+        }
+    }
+    if let Some(iri) = iri {
+        *node = NamedNode::new_unchecked(iri);
+    }
+}
+
+/// Returns `iri` without its trailing slash, or `None` if it has none.
+///
+/// The slash is kept when it follows another one, as in `file:///`, so that the
+/// IRI stays absolute.
+pub(crate) fn trim_trailing_slash(iri: &str) -> Option<&str> {
+    iri.strip_suffix('/')
+        .filter(|trimmed| !trimmed.ends_with('/'))
+}
+
+/// Returns `iri` with percent-encoded unreserved characters decoded and the
+/// hex digits of the remaining percent-encodings uppercase, or `None` if it is
+/// unchanged.
+///
+/// These are the percent-encoding normalizations of RFC 3986 section 6.2.2,
+/// which never change what the IRI identifies. Encoded reserved characters,
+/// such as `%2F`, are kept since decoding them would, and so are encoded
+/// non-ASCII characters.
+pub(crate) fn normalize_iri(iri: &str) -> Option<String> {
+    if !iri.contains('%') {
+        return None;
+    }
+    let bytes = iri.as_bytes();
+    let mut normalized = String::with_capacity(iri.len());
+    let mut start = 0;
+    let mut i = 0;
+    while i + 2 < bytes.len() {
+        if bytes[i] != b'%' {
+            i += 1;
+            continue;
+        }
+        let (Some(high), Some(low)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) else {
+            i += 1;
+            continue;
+        };
+        normalized.push_str(&iri[start..i]);
+        let decoded = high << 4 | low;
+        if decoded.is_ascii_alphanumeric() || b"-._~".contains(&decoded) {
+            normalized.push(decoded as char);
+        } else {
+            normalized.push('%');
+            normalized.push_str(&iri[i + 1..i + 3].to_ascii_uppercase());
+        }
+        i += 3;
+        start = i;
+    }
+    normalized.push_str(&iri[start..]);
+    (normalized != iri).then_some(normalized)
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_encoding() {
+        let normalize = |iri| normalize_iri(iri).unwrap_or_else(|| iri.to_string());
+        assert_eq!(
+            normalize("http://example.org/a%2fb%2Fc"),
+            "http://example.org/a%2Fb%2Fc"
+        );
+        assert_eq!(
+            normalize("http://example.org/%7euser"),
+            "http://example.org/~user"
+        );
+        assert_eq!(
+            normalize("http://example.org/%41%2d%5F"),
+            "http://example.org/A-_"
+        );
+        assert_eq!(
+            normalize("http://example.org/caf%c3%a9"),
+            "http://example.org/caf%C3%A9"
+        );
+        assert_eq!(normalize("http://example.org/%2"), "http://example.org/%2");
+        assert_eq!(normalize_iri("http://example.org/a%20b"), None);
+        assert_eq!(normalize_iri("http://example.org/é"), None);
+    }
+
+    #[test]
+    fn trailing_slash() {
+        assert_eq!(
+            trim_trailing_slash("http://example.org/ns/"),
+            Some("http://example.org/ns")
+        );
+        assert_eq!(trim_trailing_slash("http://example.org/ns"), None);
+        assert_eq!(trim_trailing_slash("file:///"), None);
+        assert_eq!(trim_trailing_slash("http://example.org/ns#"), None);
+    }
+}
@@ -1,5 +1,18 @@
 // Copyright (c) 2025, Decisym, LLC
 // Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
 
+pub mod bitmap_triples;
+pub mod bnodes;
 pub mod builder;
+pub mod dictionary;
+pub(crate) mod empty_hdt;
+pub mod error;
+pub(crate) mod header;
+pub mod integrity;
+pub(crate) mod iris;
+pub(crate) mod literals;
+pub(crate) mod manifest;
+pub(crate) mod prefix_scanner;
 pub(crate) mod rdf_reader;
+pub(crate) mod utf8_reader;
+pub mod vocab;
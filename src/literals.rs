@@ -0,0 +1,284 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use crate::builder::Options;
+use oxrdf::{Literal, Term, Triple, vocab::xsd};
+
+/// Rewrites the literals of `triple`, including those of quoted triples,
+/// according to the literal options of `opts`.
+pub(crate) fn normalize_literals(triple: &mut Triple, opts: &Options) {
+    match &mut triple.object {
+        Term::Literal(literal) => {
+            if let Some(normalized) = normalize_literal(literal, opts) {
+                *literal = normalized;
+            }
+        }
+        Term::Triple(quoted) => normalize_literals(quoted, opts),
+        _ => {}
+    }
+}
+
+/// Returns the normalized form of `literal`, or `None` if it is unchanged.
+fn normalize_literal(literal: &Literal, opts: &Options) -> Option<Literal> {
+    match literal.language() {
+        Some(language) if opts.normalize_lang_tags => normalize_language(literal, language),
+        Some(_) => None,
+        None if opts.canonicalize_literals => canonicalize_typed(literal),
+        None => None,
+    }
+}
+
+fn normalize_language(literal: &Literal, language: &str) -> Option<Literal> {
+    let normalized = normalize_lang_tag(language);
+    if normalized == language {
+        return None;
+    }
+    Some(match literal.direction() {
+        Some(direction) => Literal::new_directional_language_tagged_literal_unchecked(
+            literal.value(),
+            normalized,
+            direction,
+        ),
+        None => Literal::new_language_tagged_literal_unchecked(literal.value(), normalized),
+    })
+}
+
+/// Applies the BCP 47 casing conventions to a well-formed language tag.
+///
+/// The language is lowercase, a 4-letter script titlecase and a 2-letter region
+/// uppercase, e.g. `zh-Hant-TW`. Everything else, including the subtags
+/// following a singleton such as `x-` or `u-`, is lowercase.
+pub(crate) fn normalize_lang_tag(tag: &str) -> String {
+    let mut normalized = String::with_capacity(tag.len());
+    let mut after_singleton = false;
+    for (i, subtag) in tag.split('-').enumerate() {
+        if i > 0 {
+            normalized.push('-');
+        }
+        if i == 0 || after_singleton {
+            normalized.push_str(&subtag.to_ascii_lowercase());
+        } else if subtag.len() == 1 {
+            after_singleton = true;
+            normalized.push_str(&subtag.to_ascii_lowercase());
+        } else if subtag.len() == 2 {
+            normalized.push_str(&subtag.to_ascii_uppercase());
+        } else if subtag.len() == 4 && subtag.bytes().all(|b| b.is_ascii_alphabetic()) {
+            normalized.push_str(&subtag[..1].to_ascii_uppercase());
+            normalized.push_str(&subtag[1..].to_ascii_lowercase());
+        } else {
+            normalized.push_str(&subtag.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// Maps a literal of one of the supported XSD datatypes to its canonical lexical
+/// form, see [`Options::canonicalize_literals`].
+fn canonicalize_typed(literal: &Literal) -> Option<Literal> {
+    let datatype = literal.datatype();
+    let value = literal.value();
+    let canonical = if datatype == xsd::INTEGER {
+        canonical_integer(value)
+    } else if datatype == xsd::DECIMAL {
+        canonical_decimal(value)
+    } else if datatype == xsd::BOOLEAN {
+        canonical_boolean(value)
+    } else if datatype == xsd::DOUBLE {
+        canonical_double(value)
+    } else {
+        None
+    }?;
+    if canonical == value {
+        return None;
+    }
+    Some(Literal::new_typed_literal(canonical, datatype))
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Splits the sign off a numeric lexical form, returning whether it is negative.
+fn split_sign(value: &str) -> (bool, &str) {
+    match value.as_bytes().first() {
+        Some(b'-') => (true, &value[1..]),
+        Some(b'+') => (false, &value[1..]),
+        _ => (false, value),
+    }
+}
+
+/// `xsd:integer`: no sign unless negative, no leading zeros.
+fn canonical_integer(value: &str) -> Option<String> {
+    let (negative, digits) = split_sign(value.trim());
+    if !is_digits(digits) {
+        return None;
+    }
+    let digits = digits.trim_start_matches('0');
+    Some(match digits {
+        "" => "0".to_string(),
+        _ if negative => format!("-{digits}"),
+        _ => digits.to_string(),
+    })
+}
+
+/// `xsd:decimal`, as in XSD 1.1: like an integer when the value is integral,
+/// otherwise without leading or trailing zeros besides the one before the point.
+fn canonical_decimal(value: &str) -> Option<String> {
+    let (negative, unsigned) = split_sign(value.trim());
+    let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if !(int_part.is_empty() || is_digits(int_part))
+        || !(frac_part.is_empty() || is_digits(frac_part))
+        || int_part.len() + frac_part.len() == 0
+    {
+        return None;
+    }
+    let int_part = match int_part.trim_start_matches('0') {
+        "" => "0",
+        digits => digits,
+    };
+    let frac_part = frac_part.trim_end_matches('0');
+    let sign = if negative && (int_part != "0" || !frac_part.is_empty()) {
+        "-"
+    } else {
+        ""
+    };
+    Some(match frac_part {
+        "" => format!("{sign}{int_part}"),
+        _ => format!("{sign}{int_part}.{frac_part}"),
+    })
+}
+
+/// `xsd:boolean`: `true` or `false` rather than `1` or `0`.
+fn canonical_boolean(value: &str) -> Option<String> {
+    match value.trim() {
+        "true" | "1" => Some("true".to_string()),
+        "false" | "0" => Some("false".to_string()),
+        _ => None,
+    }
+}
+
+/// `xsd:double`: scientific notation with a single digit before the point and at
+/// least one after it, e.g. `1.5E2`, or `INF`, `-INF` and `NaN`.
+///
+/// The value is rounded to the nearest double, and written with the fewest
+/// digits that read back as that double.
+fn canonical_double(value: &str) -> Option<String> {
+    let value = value.trim();
+    match value {
+        "INF" | "+INF" => return Some("INF".to_string()),
+        "-INF" => return Some("-INF".to_string()),
+        "NaN" => return Some("NaN".to_string()),
+        _ => {}
+    }
+    // f64 parsing also accepts forms such as "inf" that aren't valid XSD
+    let (mantissa, exponent) = value
+        .split_once(['e', 'E'])
+        .map_or((value, None), |(m, e)| (m, Some(e)));
+    canonical_decimal(mantissa)?;
+    if let Some(exponent) = exponent {
+        canonical_integer(exponent)?;
+    }
+    let double: f64 = value.parse().ok()?;
+    let formatted = format!("{double:E}");
+    let (mantissa, exponent) = formatted.split_once('E')?;
+    Some(if mantissa.contains('.') {
+        formatted
+    } else {
+        format!("{mantissa}.0E{exponent}")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::build_hdt;
+    use crate::dictionary::{Section, term_sections};
+    use crate::error::Rdf2HdtError;
+
+    #[test]
+    fn lang_tag_casing() {
+        assert_eq!(normalize_lang_tag("EN"), "en");
+        assert_eq!(normalize_lang_tag("en-us"), "en-US");
+        assert_eq!(normalize_lang_tag("ZH-hant-tw"), "zh-Hant-TW");
+        assert_eq!(normalize_lang_tag("es-419"), "es-419");
+        assert_eq!(normalize_lang_tag("de-CH-1996"), "de-CH-1996");
+        assert_eq!(normalize_lang_tag("en-a-BB-x-PRIV"), "en-a-bb-x-priv");
+        assert_eq!(normalize_lang_tag("X-Private"), "x-private");
+    }
+
+    #[test]
+    fn canonical_numbers() {
+        assert_eq!(canonical_integer("+007").as_deref(), Some("7"));
+        assert_eq!(canonical_integer("-0").as_deref(), Some("0"));
+        assert_eq!(canonical_integer("-012").as_deref(), Some("-12"));
+        assert_eq!(canonical_integer("1.0"), None);
+        assert_eq!(canonical_decimal("01.50").as_deref(), Some("1.5"));
+        assert_eq!(canonical_decimal("2.000").as_deref(), Some("2"));
+        assert_eq!(canonical_decimal("-.5").as_deref(), Some("-0.5"));
+        assert_eq!(canonical_decimal("-0.0").as_deref(), Some("0"));
+        assert_eq!(canonical_decimal("."), None);
+        assert_eq!(canonical_boolean("1").as_deref(), Some("true"));
+        assert_eq!(canonical_boolean("no"), None);
+        assert_eq!(canonical_double("100").as_deref(), Some("1.0E2"));
+        assert_eq!(canonical_double("0.15e1").as_deref(), Some("1.5E0"));
+        assert_eq!(canonical_double("-0").as_deref(), Some("-0.0E0"));
+        assert_eq!(canonical_double("+INF").as_deref(), Some("INF"));
+        assert_eq!(canonical_double("inf"), None);
+    }
+
+    #[test]
+    fn canonical_literals() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let opts = Options {
+            canonicalize_literals: true,
+            ..Default::default()
+        };
+        let hdt = build_hdt(
+            vec!["tests/resources/typed_literals.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &opts,
+        )?;
+        let xsd =
+            |value, datatype| format!("\"{value}\"^^<http://www.w3.org/2001/XMLSchema#{datatype}>");
+        // "01" and "1", "1" and "true" are the same values
+        assert_eq!(Section::Objects.ids(&hdt).count(), 4);
+        assert_eq!(
+            term_sections(&hdt, &xsd("1", "integer")),
+            [Section::Objects]
+        );
+        assert_eq!(
+            term_sections(&hdt, &xsd("true", "boolean")),
+            [Section::Objects]
+        );
+        assert_eq!(
+            term_sections(&hdt, &xsd("1.0E1", "double")),
+            [Section::Objects]
+        );
+        // unknown datatypes are left as is
+        assert_eq!(term_sections(&hdt, &xsd("01", "int")), [Section::Objects]);
+        Ok(())
+    }
+
+    #[test]
+    fn normalized_lang_tags() -> Result<(), Rdf2HdtError> {
+        let tmp_hdt = tempfile::Builder::new().suffix(".hdt").tempfile()?;
+        let opts = Options {
+            normalize_lang_tags: true,
+            ..Default::default()
+        };
+        let hdt = build_hdt(
+            vec!["tests/resources/lang_tags.ttl".to_string()],
+            tmp_hdt.path().to_str().unwrap(),
+            &opts,
+        )?;
+        // "foo"@EN and "foo"@en are the same object
+        assert_eq!(Section::Objects.ids(&hdt).count(), 4);
+        assert_eq!(term_sections(&hdt, "\"foo\"@en"), [Section::Objects]);
+        assert_eq!(term_sections(&hdt, "\"color\"@en-US"), [Section::Objects]);
+        assert_eq!(
+            term_sections(&hdt, "\"中文\"@zh-Hant-TW"),
+            [Section::Objects]
+        );
+        Ok(())
+    }
+}
@@ -25,9 +25,16 @@
 //! rdf2hdt convert --input data.ttl --output result.hdt
 //! ```
 //! This will take `data.ttl`, convert to NTriple, and generate and save the HDT output to `result.hdt`.
+//!
+//! RDF can also be piped in through stdin, in which case the format must be given:
+//! ```
+//! cat data.nt | rdf2hdt convert --input - --format nt --output result.hdt
+//! ```
 
 use clap::{Parser, Subcommand};
-use rdf2hdt::builder::build_hdt;
+use rdf2hdt::builder::{Options, RdfFormat, build_hdt};
+use std::path::PathBuf;
+use std::process::ExitCode;
 
 /// Command-line interface for rdf2hdt Converter
 ///
@@ -55,19 +62,59 @@ enum Commands {
         /// Path to input RDF file(s).
         ///
         /// Provide the path to one or more RDF files that will be parsed and converted.
-        /// Support file formats: https://crates.io/crates/oxrdfio
+        /// Support file formats: https://crates.io/crates/oxrdfio,
+        /// plus `.owl` for RDF/XML and `.json` for JSON-LD.
+        /// Gzip-compressed files (e.g. `data.ttl.gz`) are decompressed on the fly.
+        /// Use `-` to read from stdin, together with `--format`.
         #[arg(short, long, num_args = 1..)]
         input: Vec<String>,
 
+        /// Format of the input, replacing the one given by the file extensions.
+        ///
+        /// Given as a file extension (e.g. `nt`, `ttl`) or a media type (e.g. `text/turtle`).
+        /// Required when reading from stdin, also useful for files without an extension.
+        #[arg(short, long)]
+        format: Option<String>,
+
         /// Path to output file.
         ///
         /// Specify the path to save the generated HDT.
         #[arg(short, long)]
         output: String,
+
+        /// Directory for intermediate files.
+        ///
+        /// Defaults to the system temp directory.
+        #[arg(long)]
+        temp_dir: Option<PathBuf>,
+
+        /// Skip invalid statements instead of failing.
+        ///
+        /// Invalid statements are logged as warnings.
+        #[arg(long)]
+        lenient: bool,
+
+        /// Read the HDT back after writing it and check it matches.
+        ///
+        /// Doubles the I/O of a conversion.
+        #[arg(long)]
+        verify: bool,
+
+        /// Maximum number of threads parsing input files in parallel.
+        ///
+        /// Defaults to one thread per CPU.
+        #[arg(long)]
+        parser_threads: Option<usize>,
+
+        /// Base IRI for relative IRIs in documents that declare none.
+        ///
+        /// Defaults to `file://` followed by the input file name.
+        #[arg(long)]
+        base_iri: Option<String>,
     },
 }
 
-fn main() {
+fn main() -> ExitCode {
     let cli = Cli::parse();
 
     env_logger::Builder::new()
@@ -75,10 +122,43 @@ fn main() {
         .init();
 
     match &cli.command {
-        Some(Commands::Convert { input, output }) => match build_hdt(input.clone(), output) {
-            Ok(_) => {}
-            Err(e) => eprintln!("Error writing: {}", e),
-        },
+        Some(Commands::Convert {
+            input,
+            output,
+            format,
+            temp_dir,
+            lenient,
+            verify,
+            parser_threads,
+            base_iri,
+        }) => {
+            let input_format = match format {
+                Some(f) => {
+                    match RdfFormat::from_extension(f).or_else(|| RdfFormat::from_media_type(f)) {
+                        Some(t) => Some(t),
+                        None => {
+                            eprintln!("Unrecognized RDF format: {}", f);
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                }
+                None => None,
+            };
+            let opts = Options {
+                input_format,
+                temp_dir: temp_dir.clone(),
+                lenient: *lenient,
+                verify: *verify,
+                parser_threads: *parser_threads,
+                base_iri: base_iri.clone(),
+                ..Default::default()
+            };
+            if let Err(e) = build_hdt(input.clone(), output, &opts) {
+                eprintln!("Error writing: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
         None => {}
     }
+    ExitCode::SUCCESS
 }
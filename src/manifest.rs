@@ -0,0 +1,219 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use crate::builder::{BuildStats, Options};
+use crate::error::Rdf2HdtError;
+use crate::header::{build_time, utc_timestamp};
+use crate::integrity::CRC32C;
+use crate::rdf_reader::STDIN_PATH;
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::Path,
+};
+
+/// Writes to `manifest` the JSON description of the build of `dest_file` from
+/// `file_paths`, see [`Options::emit_manifest`].
+pub(crate) fn write_manifest(
+    manifest: &Path,
+    file_paths: &[String],
+    dest_file: &str,
+    stats: &BuildStats,
+    opts: &Options,
+) -> Result<(), Rdf2HdtError> {
+    let sources = file_paths
+        .iter()
+        .map(|path| source(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let output = object(&[
+        ("path", string(dest_file)),
+        ("bytes", std::fs::metadata(dest_file)?.len().to_string()),
+    ]);
+    let json = object(&[
+        ("tool", string(env!("CARGO_PKG_NAME"))),
+        ("version", string(env!("CARGO_PKG_VERSION"))),
+        ("created", string(&utc_timestamp(build_time()))),
+        ("output", output),
+        ("sources", array(sources)),
+        ("stats", stats_object(stats)),
+        ("options", options_object(opts)),
+    ]);
+    let mut writer = BufWriter::new(File::create(manifest)?);
+    writeln!(writer, "{json}")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Size and CRC32C of an input file, both `null` for stdin.
+fn source(path: &str) -> Result<String, Rdf2HdtError> {
+    if path == STDIN_PATH {
+        return Ok(object(&[
+            ("path", string(path)),
+            ("bytes", "null".to_string()),
+            ("crc32c", "null".to_string()),
+        ]));
+    }
+    let mut file = File::open(path)?;
+    let mut digest = CRC32C.digest();
+    let mut buf = vec![0; 64 * 1024];
+    let mut bytes = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        digest.update(&buf[..n]);
+        bytes += n as u64;
+    }
+    Ok(object(&[
+        ("path", string(path)),
+        ("bytes", bytes.to_string()),
+        ("crc32c", string(&format!("{:08x}", digest.finalize()))),
+    ]))
+}
+
+fn stats_object(stats: &BuildStats) -> String {
+    object(&[
+        ("input_triples", stats.input_triples.to_string()),
+        ("skipped", stats.skipped.to_string()),
+        ("excluded", stats.excluded.to_string()),
+        ("not_included", stats.not_included.to_string()),
+        ("filtered", stats.filtered.to_string()),
+        ("pruned_terms", stats.pruned_terms.to_string()),
+        ("input_bytes", stats.input_bytes.to_string()),
+        ("duplicates", stats.duplicates.to_string()),
+        ("triples", stats.triples.to_string()),
+        ("subjects", stats.subjects.to_string()),
+        ("predicates", stats.predicates.to_string()),
+        ("objects", stats.objects.to_string()),
+        ("shared", stats.shared.to_string()),
+        ("elapsed_ms", stats.elapsed.as_millis().to_string()),
+        (
+            "warnings",
+            array(stats.warnings.iter().map(|w| string(w)).collect()),
+        ),
+    ])
+}
+
+/// The options that change which triples and terms end up in the HDT.
+fn options_object(opts: &Options) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+    let strings = |values: &[String]| array(values.iter().map(|v| string(v)).collect());
+    let mut metadata = opts.header_metadata.iter().collect::<Vec<_>>();
+    metadata.sort_unstable();
+    let metadata = metadata
+        .into_iter()
+        .map(|(property, value)| (property.as_str(), string(value)))
+        .collect::<Vec<_>>();
+    let mut file_formats = opts.file_formats.iter().collect::<Vec<_>>();
+    file_formats.sort_unstable_by_key(|(path, _)| *path);
+    let file_formats = file_formats
+        .into_iter()
+        .map(|(path, format)| (path.as_str(), string(format.name())))
+        .collect::<Vec<_>>();
+    object(&[
+        (
+            "input_format",
+            optional(opts.input_format.map(|f| string(f.name()))),
+        ),
+        ("file_formats", object(&file_formats)),
+        ("lenient", opts.lenient.to_string()),
+        ("canonical_bnodes", opts.canonical_bnodes.to_string()),
+        // callbacks can't be described, only whether one is set
+        (
+            "bnode_canonicalizer",
+            opts.bnode_canonicalizer.is_some().to_string(),
+        ),
+        ("shared_bnode_scope", opts.shared_bnode_scope.to_string()),
+        ("normalize_lang_tags", opts.normalize_lang_tags.to_string()),
+        (
+            "canonicalize_literals",
+            opts.canonicalize_literals.to_string(),
+        ),
+        ("normalize_iris", opts.normalize_iris.to_string()),
+        (
+            "canonical_trailing_slash",
+            opts.canonical_trailing_slash.to_string(),
+        ),
+        ("rdf_star", string(&format!("{:?}", opts.rdf_star))),
+        (
+            "generalized_rdf",
+            string(&format!("{:?}", opts.generalized_rdf)),
+        ),
+        (
+            "resolve_remote_contexts",
+            opts.resolve_remote_contexts.to_string(),
+        ),
+        ("base_iri", optional(opts.base_iri.as_deref().map(string))),
+        (
+            "max_triples",
+            optional(opts.max_triples.map(|m| m.to_string())),
+        ),
+        (
+            "max_distinct_terms",
+            optional(opts.max_distinct_terms.map(|m| m.to_string())),
+        ),
+        ("allow_empty", opts.allow_empty.to_string()),
+        ("exclude_predicates", strings(&opts.exclude_predicates)),
+        (
+            "include_only_predicates",
+            optional(opts.include_only_predicates.as_deref().map(strings)),
+        ),
+        ("triple_filter", opts.triple_filter.is_some().to_string()),
+        (
+            "dictionary_format",
+            string(&format!("{:?}", opts.dictionary_format)),
+        ),
+        ("header_metadata", object(&metadata)),
+    ])
+}
+
+fn object(fields: &[(&str, String)]) -> String {
+    let fields = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{value}", string(key)))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(","))
+}
+
+fn array(values: Vec<String>) -> String {
+    format!("[{}]", values.join(","))
+}
+
+/// `s` as a JSON string.
+fn string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_strings() {
+        assert_eq!(string("a\"b\\c"), r#""a\"b\\c""#);
+        assert_eq!(string("line\nnext\u{1}"), r#""line\nnext\u0001""#);
+        assert_eq!(string("μ"), "\"μ\"");
+        assert_eq!(
+            object(&[("a", "1".to_string()), ("b", array(vec![string("x")]))]),
+            r#"{"a":1,"b":["x"]}"#
+        );
+    }
+}
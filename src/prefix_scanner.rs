@@ -0,0 +1,204 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use log::warn;
+use oxrdf::{Quad, Triple};
+use oxttl::{
+    N3Quad, TurtleParseError, TurtleSyntaxError, n3::LowLevelN3Parser, trig::LowLevelTriGParser,
+    turtle::LowLevelTurtleParser,
+};
+use std::{cell::RefCell, collections::HashMap, io::BufRead, rc::Rc};
+
+/// Push parser of a Turtle-like syntax, fed by [`PrefixScanner`].
+pub(crate) trait LowLevelParser {
+    type Item;
+    fn extend_from_slice(&mut self, bytes: &[u8]);
+    fn end(&mut self);
+    fn is_end(&self) -> bool;
+    fn parse_next(&mut self) -> Option<Result<Self::Item, TurtleSyntaxError>>;
+    fn prefixes(&self) -> impl Iterator<Item = (&str, &str)>;
+    fn base_iri(&self) -> Option<&str>;
+}
+
+macro_rules! impl_low_level_parser {
+    ($parser:ty, $item:ty) => {
+        impl LowLevelParser for $parser {
+            type Item = $item;
+            fn extend_from_slice(&mut self, bytes: &[u8]) {
+                self.extend_from_slice(bytes)
+            }
+            fn end(&mut self) {
+                self.end()
+            }
+            fn is_end(&self) -> bool {
+                self.is_end()
+            }
+            fn parse_next(&mut self) -> Option<Result<$item, TurtleSyntaxError>> {
+                self.parse_next()
+            }
+            fn prefixes(&self) -> impl Iterator<Item = (&str, &str)> {
+                self.prefixes()
+            }
+            fn base_iri(&self) -> Option<&str> {
+                self.base_iri()
+            }
+        }
+    };
+}
+
+impl_low_level_parser!(LowLevelTurtleParser, Triple);
+impl_low_level_parser!(LowLevelTriGParser, Quad);
+impl_low_level_parser!(LowLevelN3Parser, N3Quad);
+
+/// Parses Turtle, TriG or N3 input fed one line at a time, looking for
+/// `@prefix`, `PREFIX`, `@base` and `BASE` directives that change an earlier
+/// binding.
+///
+/// Redefinitions are legal, the parser applies them as usual, but are logged and
+/// collected in `warnings` since they often come from concatenated files. The
+/// bindings are compared as the parser resolved them, each time it has parsed
+/// all it was fed, so a directive is reported on the line it ends on.
+pub(crate) struct PrefixScanner<P, R> {
+    parser: P,
+    reader: R,
+    name: String,
+    warnings: Rc<RefCell<Vec<String>>>,
+    /// 1-based line of the last byte fed to the parser.
+    line: usize,
+    /// Whether the last byte fed ended a line.
+    at_line_start: bool,
+    prefixes: HashMap<String, (String, usize)>,
+    base: Option<(String, usize)>,
+}
+
+impl<P: LowLevelParser, R: BufRead> PrefixScanner<P, R> {
+    /// Parses `reader` with `parser`, reporting redefinitions under `name` to
+    /// `warnings`.
+    pub(crate) fn new(
+        parser: P,
+        reader: R,
+        name: String,
+        warnings: Rc<RefCell<Vec<String>>>,
+    ) -> Self {
+        Self {
+            parser,
+            reader,
+            name,
+            warnings,
+            line: 0,
+            at_line_start: true,
+            prefixes: HashMap::new(),
+            base: None,
+        }
+    }
+
+    /// Compares the parser's bindings with the ones seen so far.
+    fn check_bindings(&mut self) {
+        let line = self.line;
+        let mut redefined = Vec::new();
+        for (prefix, iri) in self.parser.prefixes() {
+            match self.prefixes.get(prefix) {
+                Some((previous, _)) if previous == iri => continue,
+                Some((previous, previous_line)) => redefined.push(format!(
+                    "prefix {prefix}: <{iri}> redefines <{previous}> from line {previous_line}"
+                )),
+                None => {}
+            }
+            self.prefixes
+                .insert(prefix.to_string(), (iri.to_string(), line));
+        }
+        if let Some(iri) = self.parser.base_iri() {
+            match &self.base {
+                Some((previous, _)) if previous == iri => {}
+                previous => {
+                    if let Some((previous, previous_line)) = previous {
+                        redefined.push(format!(
+                            "base <{iri}> redefines <{previous}> from line {previous_line}"
+                        ));
+                    }
+                    self.base = Some((iri.to_string(), line));
+                }
+            }
+        }
+        for what in redefined {
+            let warning = format!("{} line {line}: {what}", self.name);
+            warn!("{warning}");
+            self.warnings.borrow_mut().push(warning);
+        }
+    }
+}
+
+impl<P: LowLevelParser, R: BufRead> Iterator for PrefixScanner<P, R> {
+    type Item = Result<P::Item, TurtleParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.parser.parse_next() {
+                return Some(result.map_err(TurtleParseError::Syntax));
+            }
+            self.check_bindings();
+            if self.parser.is_end() {
+                return None;
+            }
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(e) => return Some(Err(TurtleParseError::Io(e))),
+            };
+            if chunk.is_empty() {
+                self.parser.end();
+                continue;
+            }
+            let len = chunk
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(chunk.len(), |newline| newline + 1);
+            if self.at_line_start {
+                self.line += 1;
+            }
+            self.at_line_start = chunk[len - 1] == b'\n';
+            self.parser.extend_from_slice(&chunk[..len]);
+            self.reader.consume(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxttl::TurtleParser;
+
+    #[test]
+    fn redefinitions() {
+        let input = "@prefix ex: <http://example.org/a#>.\n\
+                     @prefix ex: <http://example.org/a#>.\n\
+                     ex:s ex:p \"\"\"\n\
+                     PREFIX ex: <http://example.org/c#>\n\
+                     \"\"\".\n\
+                     @prefix ex: <http://example.org/b#>.\n\
+                     @base <http://example.org/>.\n\
+                     @prefix rel: <r#>.\n\
+                     BASE <http://example.com/>\n\
+                     @prefix rel: <r#>.\n";
+        let warnings = Rc::new(RefCell::new(Vec::new()));
+        let scanner = PrefixScanner::new(
+            TurtleParser::new().low_level(),
+            input.as_bytes(),
+            "test.ttl".to_string(),
+            warnings.clone(),
+        );
+        assert_eq!(scanner.filter(Result::is_ok).count(), 1);
+        // the directive inside the long literal is text, and the relative prefix
+        // is redefined by the new base
+        assert_eq!(
+            *warnings.borrow(),
+            [
+                "test.ttl line 6: prefix ex: <http://example.org/b#> redefines \
+                 <http://example.org/a#> from line 1",
+                "test.ttl line 9: base <http://example.com/> redefines <http://example.org/> \
+                 from line 7",
+                "test.ttl line 10: prefix rel: <http://example.com/r#> redefines \
+                 <http://example.org/r#> from line 8",
+            ]
+        );
+    }
+}
@@ -1,89 +1,1152 @@
 // Copyright (c) 2025, Decisym, LLC
 // Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
 
+use crate::bnodes::{BnodeCanonicalizer, CounterCanonicalizer};
+use crate::builder::{BuildPhase, GeneralizedRdfMode, Options, PROGRESS_INTERVAL, RdfStarMode};
+use crate::error::{Rdf2HdtError, position};
+use crate::iris::normalize_iris;
+use crate::literals::normalize_literals;
+use crate::prefix_scanner::PrefixScanner;
+use crate::utf8_reader::{InvalidUtf8, UTF8_BOM, Utf8Reader};
+use crate::vocab::{compact_iri_with, is_unknown_datatype};
+use flate2::read::MultiGzDecoder;
 use log::{debug, error, warn};
-use oxrdfio::RdfSerializer;
+#[cfg(feature = "remote-contexts")]
+use oxjsonld::{JsonLdLoadDocumentOptions, JsonLdParser, JsonLdRemoteDocument};
+use oxrdf::{
+    BaseDirection, BlankNode, GraphName, Literal, NamedNode, NamedOrBlankNode, Quad, Term, Triple,
+    vocab::rdf,
+};
 use oxrdfio::{
     RdfFormat::{self, NTriples},
-    RdfParseError, RdfParser,
+    RdfParseError, RdfParser, RdfSerializer,
 };
+use oxttl::{N3Parser, N3Quad, N3Term, TriGParser, TurtleParser};
+use rayon::prelude::*;
 use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::{
-    error::Error,
-    io::{BufReader, BufWriter},
+    cell::RefCell,
+    collections::{HashSet, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read},
     path::Path,
+    rc::Rc,
 };
 
-pub(crate) fn convert_to_nt(
-    file_paths: Vec<String>,
-    output_file: std::fs::File,
-) -> Result<(), Box<dyn Error>> {
-    let mut dest_writer = BufWriter::new(output_file);
-    for file in file_paths {
-        let source = match std::fs::File::open(&file) {
-            Ok(f) => f,
-            Err(e) => {
-                error!("Error opening file {file:?}: {e:?}");
-                return Err(e.into());
+/// Name of the input read by [`FileReader::from_bytes`] in messages.
+const IN_MEMORY_NAME: &str = "<in-memory input>";
+
+/// Input path that selects stdin instead of a file.
+pub(crate) const STDIN_PATH: &str = "-";
+
+/// Leading bytes of every gzip member.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Pull-based source of parsed RDF triples.
+///
+/// Implementations yield one triple at a time so callers never need to hold the
+/// whole dataset in memory.
+pub(crate) trait RdfReader {
+    /// Returns the next parsed triple, or `None` once the source is exhausted.
+    fn next_triple(&mut self) -> Option<Result<Triple, Rdf2HdtError>>;
+}
+
+/// Reads the triples of `.0`, then those of `.1`.
+pub(crate) struct Chain<A, B>(pub(crate) A, pub(crate) B);
+
+impl<A: RdfReader, B: RdfReader> RdfReader for Chain<A, B> {
+    fn next_triple(&mut self) -> Option<Result<Triple, Rdf2HdtError>> {
+        self.0.next_triple().or_else(|| self.1.next_triple())
+    }
+}
+
+/// Streams the triples of an existing HDT, so they can be merged with new input.
+///
+/// Blank nodes are relabeled `h0`, `h1`, ..., keeping them apart from the blank
+/// nodes of the new input.
+pub(crate) struct HdtReader<'a> {
+    triples: Box<dyn Iterator<Item = [std::sync::Arc<str>; 3]> + 'a>,
+    bnodes: CounterCanonicalizer,
+}
+
+impl<'a> HdtReader<'a> {
+    pub(crate) fn new(hdt: &'a hdt::Hdt) -> Self {
+        Self {
+            triples: Box::new(hdt.triples_with_pattern(None, None, None)),
+            bnodes: CounterCanonicalizer::new("h"),
+        }
+    }
+
+    fn triple(&mut self, [s, p, o]: [std::sync::Arc<str>; 3]) -> Result<Triple, Rdf2HdtError> {
+        let subject = match hdt_term(&s)? {
+            Term::NamedNode(n) => NamedOrBlankNode::NamedNode(n),
+            Term::BlankNode(b) => NamedOrBlankNode::BlankNode(b),
+            _ => return Err(unexpected_hdt_term(&s)),
+        };
+        let predicate = NamedNode::new(&*p).map_err(|_| unexpected_hdt_term(&p))?;
+        let mut triple = Triple::new(subject, predicate, hdt_term(&o)?);
+        self.bnodes.canonicalize(&mut triple);
+        Ok(triple)
+    }
+}
+
+impl RdfReader for HdtReader<'_> {
+    fn next_triple(&mut self) -> Option<Result<Triple, Rdf2HdtError>> {
+        let triple = self.triples.next()?;
+        Some(self.triple(triple))
+    }
+}
+
+/// Reads the triples of `inner` until `max` have been read and the triples of
+/// the last subject are complete, leaving the first triple of the next shard in
+/// `next`.
+///
+/// `inner` must list the triples of each subject together, as [`HdtReader`] does.
+pub(crate) struct Shard<'r, R> {
+    inner: &'r mut R,
+    next: &'r mut Option<Triple>,
+    max: u64,
+    count: u64,
+    subject: Option<NamedOrBlankNode>,
+}
+
+impl<'r, R: RdfReader> Shard<'r, R> {
+    pub(crate) fn new(inner: &'r mut R, next: &'r mut Option<Triple>, max: u64) -> Self {
+        Self {
+            inner,
+            next,
+            max,
+            count: 0,
+            subject: None,
+        }
+    }
+}
+
+impl<R: RdfReader> RdfReader for Shard<'_, R> {
+    fn next_triple(&mut self) -> Option<Result<Triple, Rdf2HdtError>> {
+        let triple = match self.next.take() {
+            Some(triple) => triple,
+            None => match self.inner.next_triple()? {
+                Ok(triple) => triple,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        if self.count >= self.max && self.subject.as_ref() != Some(&triple.subject) {
+            *self.next = Some(triple);
+            return None;
+        }
+        self.count += 1;
+        self.subject = Some(triple.subject.clone());
+        Some(Ok(triple))
+    }
+}
+
+/// Parses a term as stored in the HDT dictionary: IRIs without angle brackets,
+/// literals and blank nodes as in N-Triples but with unescaped values.
+fn hdt_term(term: &str) -> Result<Term, Rdf2HdtError> {
+    if let Some(label) = term.strip_prefix("_:") {
+        return BlankNode::new(label)
+            .map(Term::from)
+            .map_err(|_| unexpected_hdt_term(term));
+    }
+    if !term.starts_with('"') {
+        return NamedNode::new(term)
+            .map(Term::from)
+            .map_err(|_| unexpected_hdt_term(term));
+    }
+    let end = term.rfind('"').filter(|end| *end > 0);
+    let Some(end) = end else {
+        return Err(unexpected_hdt_term(term));
+    };
+    let value = &term[1..end];
+    let suffix = &term[end + 1..];
+    let literal = if suffix.is_empty() {
+        Literal::new_simple_literal(value)
+    } else if let Some(language) = suffix.strip_prefix('@') {
+        Literal::new_language_tagged_literal(value, language)
+            .map_err(|_| unexpected_hdt_term(term))?
+    } else if let Some(datatype) = suffix.strip_prefix("^^<").and_then(|d| d.strip_suffix('>')) {
+        let datatype = NamedNode::new(datatype).map_err(|_| unexpected_hdt_term(term))?;
+        Literal::new_typed_literal(value, datatype)
+    } else {
+        return Err(unexpected_hdt_term(term));
+    };
+    Ok(literal.into())
+}
+
+/// Writes a term as stored in the HDT dictionary, see [`hdt_term`].
+pub(crate) fn hdt_string(term: &Term) -> String {
+    let Term::Literal(literal) = term else {
+        return match term {
+            Term::NamedNode(n) => n.as_str().to_string(),
+            _ => term.to_string(),
+        };
+    };
+    let value = literal.value();
+    match (literal.language(), literal.direction()) {
+        (Some(language), None) => format!("\"{value}\"@{language}"),
+        (Some(language), Some(BaseDirection::Ltr)) => format!("\"{value}\"@{language}--ltr"),
+        (Some(language), Some(BaseDirection::Rtl)) => format!("\"{value}\"@{language}--rtl"),
+        (None, _) if literal.is_plain() => format!("\"{value}\""),
+        (None, _) => format!("\"{value}\"^^<{}>", literal.datatype().as_str()),
+    }
+}
+
+fn unexpected_hdt_term(term: &str) -> Rdf2HdtError {
+    Rdf2HdtError::InvalidInput(format!("unexpected term in the existing HDT: {term}"))
+}
+
+/// Streams the triples of a list of RDF files, one file after the other.
+///
+/// The parser for each file is picked from its extension. Gzip-compressed files
+/// (`.gz` suffix or gzip magic bytes) are decompressed on the fly and parsed
+/// according to the extension preceding `.gz`. A path of `-` reads from stdin
+/// using [`Options::input_format`]. [`Options::file_formats`] and then
+/// [`Options::input_format`] override the extensions. Named graphs are merged
+/// into the default graph since HDT has no notion of them.
+///
+/// When several files are read, blank node labels of each file are prefixed with
+/// the position of the file, so files don't share blank nodes, unless
+/// [`Options::shared_bnode_scope`] is set.
+/// Blank nodes generated while expanding JSON-LD are always relabeled in order of
+/// first appearance, so repeated conversions of the same document match. They are
+/// scoped to their document, as JSON-LD mandates.
+///
+/// With [`Options::lenient`], invalid statements are logged and skipped rather
+/// than returned as errors, and invalid UTF-8 is replaced with U+FFFD. Offsets of
+/// invalid UTF-8 in gzip-compressed files are counted in the decompressed data.
+/// Quoted triples are handled according to [`Options::rdf_star`], and literals
+/// normalized according to [`Options::normalize_lang_tags`] and
+/// [`Options::canonicalize_literals`], and IRIs according to
+/// [`Options::normalize_iris`] and [`Options::canonical_trailing_slash`].
+/// Literals containing U+0000 are rejected, or skipped under
+/// [`Options::lenient`], since they can't be stored in the dictionary. Triples
+/// are then filtered by predicate, see [`Options::exclude_predicates`], and by
+/// [`Options::triple_filter`], and their terms counted against
+/// [`Options::max_distinct_terms`].
+pub(crate) struct FileReader<'a> {
+    files: std::vec::IntoIter<String>,
+    /// Position of the next file in the whole input, which may be split across readers.
+    next_index: usize,
+    /// Whether the input has other files, whose blank nodes must be kept apart.
+    scope_bnodes: bool,
+    current: Option<CurrentFile>,
+    opts: &'a Options,
+    /// [`Options::bnode_canonicalizer`], or the default one.
+    bnodes: Box<dyn BnodeCanonicalizer>,
+    /// Relabels the current file's JSON-LD blank nodes when
+    /// [`Options::canonical_bnodes`] is off.
+    jsonld_bnodes: CounterCanonicalizer,
+    /// Reification triples still to be returned.
+    pending: VecDeque<Triple>,
+    /// Triples returned so far, checked against [`Options::max_triples`].
+    returned: u64,
+    /// [`Options::exclude_predicates`].
+    excluded: HashSet<&'a str>,
+    /// [`Options::include_only_predicates`].
+    included: Option<HashSet<&'a str>>,
+    /// Input read by [`FileReader::from_bytes`], taken when it is opened.
+    in_memory: Option<(Vec<u8>, RdfFormat)>,
+    /// Terms of the returned triples, kept with [`Options::max_distinct_terms`].
+    terms: HashSet<String>,
+    pub(crate) stats: ReadStats,
+}
+
+/// `triple` for log messages, with IRIs shortened according to
+/// [`Options::log_prefixes`].
+fn log_triple(triple: &Triple, opts: &Options) -> String {
+    let iri = |iri: &str| {
+        let compact = compact_iri_with(iri, &opts.log_prefixes);
+        if compact == iri {
+            format!("<{iri}>")
+        } else {
+            compact
+        }
+    };
+    let subject = match &triple.subject {
+        NamedOrBlankNode::NamedNode(n) => iri(n.as_str()),
+        subject => subject.to_string(),
+    };
+    let object = match &triple.object {
+        Term::NamedNode(n) => iri(n.as_str()),
+        Term::Literal(literal) if literal.language().is_none() && !literal.is_plain() => {
+            let value = Literal::new_simple_literal(literal.value());
+            format!("{value}^^{}", iri(literal.datatype().as_str()))
+        }
+        Term::Triple(quoted) => format!("<< {} >>", log_triple(quoted, opts)),
+        object => object.to_string(),
+    };
+    format!("{subject} {} {object} .", iri(triple.predicate.as_str()))
+}
+
+/// Whether a literal of `triple`, including those of quoted triples, contains
+/// U+0000, which dictionary sections use to terminate strings.
+fn has_nul(triple: &Triple) -> bool {
+    match &triple.object {
+        Term::Literal(literal) => literal.value().contains('\0'),
+        Term::Triple(quoted) => has_nul(quoted),
+        _ => false,
+    }
+}
+
+/// The error for a literal of `file` for which [`has_nul`] holds.
+fn nul_error(file: &str) -> Rdf2HdtError {
+    Rdf2HdtError::InvalidInput(format!(
+        "{file} contains a literal with a NUL character, which the hdt crate would read back \
+         as the end of the term"
+    ))
+}
+
+/// The error for a quoted triple in `file` under [`RdfStarMode::Reject`].
+fn quoted_triple_error(file: &str) -> Rdf2HdtError {
+    Rdf2HdtError::InvalidInput(format!(
+        "{file} contains an RDF-star quoted triple, which HDT cannot encode; reify it with \
+         RdfStarMode::Reify"
+    ))
+}
+
+/// Prefixes the blank node labels of `triple`, including those of quoted triples,
+/// with `scope`.
+fn scope_bnodes(triple: &mut Triple, scope: &str) {
+    let scoped = |b: &BlankNode| BlankNode::new_unchecked(format!("{scope}{}", b.as_str()));
+    if let NamedOrBlankNode::BlankNode(b) = &mut triple.subject {
+        *b = scoped(b);
+    }
+    match &mut triple.object {
+        Term::BlankNode(b) => *b = scoped(b),
+        Term::Triple(quoted) => scope_bnodes(quoted, scope),
+        _ => {}
+    }
+}
+
+/// Picks the RDF format of a file extension.
+///
+/// Extends the extensions known to oxrdfio with `.owl`, common for RDF/XML
+/// ontologies, and `.json` for JSON-LD.
+fn format_from_extension(extension: &str) -> Option<RdfFormat> {
+    if extension.eq_ignore_ascii_case("owl") {
+        Some(RdfFormat::RdfXml)
+    } else if extension.eq_ignore_ascii_case("json") {
+        RdfFormat::from_extension("jsonld")
+    } else {
+        RdfFormat::from_extension(extension)
+    }
+}
+
+/// Parses JSON-LD, fetching the documents of remote `@context`s, see
+/// [`Options::resolve_remote_contexts`].
+#[cfg(feature = "remote-contexts")]
+fn remote_jsonld_quads(
+    reader: Box<dyn Read>,
+    base_iri: Option<String>,
+) -> Result<Box<dyn Iterator<Item = Result<Quad, RdfParseError>>>, Rdf2HdtError> {
+    // oxrdfio has no hook to load remote documents, use the JSON-LD parser directly
+    let mut parser = JsonLdParser::new().with_load_document_callback(load_remote_document);
+    if let Some(base_iri) = base_iri {
+        parser = parser
+            .with_base_iri(base_iri)
+            .map_err(|e| Rdf2HdtError::InvalidInput(format!("invalid base IRI: {e}")))?;
+    }
+    Ok(Box::new(
+        parser
+            .for_reader(reader)
+            .map(|q| q.map_err(RdfParseError::from)),
+    ))
+}
+
+#[cfg(not(feature = "remote-contexts"))]
+fn remote_jsonld_quads(
+    _reader: Box<dyn Read>,
+    _base_iri: Option<String>,
+) -> Result<Box<dyn Iterator<Item = Result<Quad, RdfParseError>>>, Rdf2HdtError> {
+    error!("remote JSON-LD contexts requested without the remote-contexts feature");
+    Err(Rdf2HdtError::InvalidInput(
+        "resolving remote JSON-LD contexts needs rdf2hdt built with the remote-contexts \
+         feature"
+            .to_string(),
+    ))
+}
+
+/// Fetches a JSON-LD document referenced by a remote `@context`, see
+/// [`Options::resolve_remote_contexts`].
+#[cfg(feature = "remote-contexts")]
+fn load_remote_document(
+    url: &str,
+    _options: &JsonLdLoadDocumentOptions,
+) -> Result<JsonLdRemoteDocument, Box<dyn std::error::Error + Send + Sync>> {
+    debug!("loading remote JSON-LD document {url}");
+    let mut response = ureq::get(url)
+        .header("Accept", "application/ld+json, application/json")
+        .call()?;
+    Ok(JsonLdRemoteDocument {
+        document: response.body_mut().read_to_vec()?,
+        document_url: url.to_string(),
+    })
+}
+
+/// A triple of N3 input that isn't valid RDF, returned inside an [`std::io::Error`]
+/// so that it goes through the parser's error type.
+#[derive(Debug)]
+struct GeneralizedTriple {
+    triple: String,
+    position: &'static str,
+}
+
+impl GeneralizedTriple {
+    fn from_io_error(e: &std::io::Error) -> Option<&Self> {
+        e.get_ref()?.downcast_ref()
+    }
+}
+
+impl std::fmt::Display for GeneralizedTriple {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "generalized triple with an invalid {}: {}",
+            self.position, self.triple
+        )
+    }
+}
+
+impl std::error::Error for GeneralizedTriple {}
+
+/// Converts an N3 quad to an RDF quad, failing with a [`GeneralizedTriple`] if
+/// one of its terms cannot be used in its position.
+fn n3_to_quad(quad: N3Quad) -> Result<Quad, RdfParseError> {
+    let generalized = |position| {
+        let triple = format!("{} {} {} .", quad.subject, quad.predicate, quad.object);
+        RdfParseError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            GeneralizedTriple { triple, position },
+        ))
+    };
+    let subject = match &quad.subject {
+        N3Term::NamedNode(n) => NamedOrBlankNode::NamedNode(n.clone()),
+        N3Term::BlankNode(b) => NamedOrBlankNode::BlankNode(b.clone()),
+        _ => return Err(generalized("subject")),
+    };
+    let N3Term::NamedNode(predicate) = &quad.predicate else {
+        return Err(generalized("predicate"));
+    };
+    let object = match &quad.object {
+        N3Term::NamedNode(n) => Term::NamedNode(n.clone()),
+        N3Term::BlankNode(b) => Term::BlankNode(b.clone()),
+        N3Term::Literal(l) => Term::Literal(l.clone()),
+        _ => return Err(generalized("object")),
+    };
+    Ok(Quad::new(
+        subject,
+        predicate.clone(),
+        object,
+        quad.graph_name.clone(),
+    ))
+}
+
+/// Replaces a quoted triple with a blank node and queues the reification
+/// triples describing it, including those of nested quoted triples.
+///
+/// The node label is a hash of the triple, so it is the same wherever and
+/// whenever the triple is converted.
+fn reify(triple: &Triple, out: &mut VecDeque<Triple>) -> BlankNode {
+    let node = BlankNode::new_unchecked(format!("t{:016x}", fnv1a(triple.to_string().as_bytes())));
+    let object = match &triple.object {
+        Term::Triple(quoted) => reify(quoted, out).into(),
+        object => object.clone(),
+    };
+    out.push_back(Triple::new(node.clone(), rdf::TYPE, rdf::STATEMENT));
+    out.push_back(Triple::new(
+        node.clone(),
+        rdf::SUBJECT,
+        triple.subject.clone(),
+    ));
+    out.push_back(Triple::new(
+        node.clone(),
+        rdf::PREDICATE,
+        triple.predicate.clone(),
+    ));
+    out.push_back(Triple::new(node.clone(), rdf::OBJECT, object));
+    node
+}
+
+/// 64-bit FNV-1a, a hash that is stable across platforms and Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Counts kept by a [`FileReader`] while parsing.
+#[derive(Debug, Default)]
+pub(crate) struct ReadStats {
+    /// Invalid statements skipped under [`Options::lenient`].
+    pub(crate) skipped: u64,
+    /// Prefix and base redefinitions found by [`PrefixScanner`], and unknown
+    /// datatypes.
+    pub(crate) warnings: Vec<String>,
+    /// Triples dropped by [`Options::exclude_predicates`].
+    pub(crate) excluded: u64,
+    /// Triples dropped by [`Options::include_only_predicates`].
+    pub(crate) not_included: u64,
+    /// Triples dropped by [`Options::triple_filter`].
+    pub(crate) filtered: u64,
+    /// Terms of the dropped triples, as stored in the HDT dictionary.
+    pub(crate) dropped_terms: HashSet<String>,
+}
+
+impl ReadStats {
+    fn drop_terms(&mut self, triple: &Triple) {
+        self.dropped_terms.extend(term_strings(triple));
+    }
+}
+
+/// The dictionary strings of the subject, predicate and object of `triple`.
+fn term_strings(triple: &Triple) -> [String; 3] {
+    [
+        hdt_string(&triple.subject.clone().into()),
+        hdt_string(&triple.predicate.clone().into()),
+        hdt_string(&triple.object),
+    ]
+}
+
+struct CurrentFile {
+    name: String,
+    quads: Box<dyn Iterator<Item = Result<Quad, RdfParseError>>>,
+    gzipped: bool,
+    jsonld: bool,
+    /// Prefix of the file's blank node labels, see [`Options::shared_bnode_scope`].
+    bnode_scope: Option<String>,
+    warned: bool,
+    /// Datatypes reported by [`is_unknown_datatype`], once per file.
+    unknown_datatypes: HashSet<String>,
+    /// Filled by the [`PrefixScanner`] reading the file, if any.
+    warnings: Rc<RefCell<Vec<String>>>,
+    timer: std::time::Instant,
+}
+
+impl CurrentFile {
+    fn close(&self, stats: &mut ReadStats) {
+        stats.warnings.append(&mut self.warnings.borrow_mut());
+    }
+}
+
+impl<'a> FileReader<'a> {
+    pub(crate) fn new(file_paths: Vec<String>, opts: &'a Options) -> Self {
+        Self {
+            scope_bnodes: file_paths.len() > 1,
+            files: file_paths.into_iter(),
+            next_index: 0,
+            current: None,
+            opts,
+            bnodes: match &opts.bnode_canonicalizer {
+                Some(canonicalizer) => canonicalizer(),
+                None => Box::new(CounterCanonicalizer::new("b")),
+            },
+            jsonld_bnodes: CounterCanonicalizer::new("j"),
+            pending: VecDeque::new(),
+            returned: 0,
+            excluded: opts.exclude_predicates.iter().map(String::as_str).collect(),
+            included: opts
+                .include_only_predicates
+                .as_ref()
+                .map(|p| p.iter().map(String::as_str).collect()),
+            in_memory: None,
+            terms: HashSet::new(),
+            stats: ReadStats::default(),
+        }
+    }
+
+    /// Reads the RDF in `bytes` rather than files, as `format`. It has no base
+    /// IRI unless [`Options::base_iri`] is set.
+    pub(crate) fn from_bytes(bytes: Vec<u8>, format: RdfFormat, opts: &'a Options) -> Self {
+        Self {
+            in_memory: Some((bytes, format)),
+            ..Self::new(vec![IN_MEMORY_NAME.to_string()], opts)
+        }
+    }
+
+    /// Sets the position of the first file in the whole input, when it is split
+    /// across several readers.
+    pub(crate) fn with_first_index(mut self, index: usize) -> Self {
+        self.next_index = index;
+        self.with_scoped_bnodes()
+    }
+
+    /// Scopes blank node labels to their file even when there is a single one,
+    /// for input merged with other triples.
+    pub(crate) fn with_scoped_bnodes(mut self) -> Self {
+        self.scope_bnodes = true;
+        self
+    }
+
+    fn open(&mut self, file: String) -> Result<CurrentFile, Rdf2HdtError> {
+        let in_memory = self.in_memory.take();
+        let in_memory_format = in_memory.as_ref().map(|(_, format)| *format);
+        let is_stdin = file == STDIN_PATH;
+        let source: Box<dyn Read> = if let Some((bytes, _)) = in_memory {
+            Box::new(std::io::Cursor::new(bytes))
+        } else if is_stdin {
+            Box::new(std::io::stdin())
+        } else {
+            match File::open(&file) {
+                Ok(f) => Box::new(f),
+                Err(e) => {
+                    error!("Error opening file {file:?}: {e:?}");
+                    return Err(e.into());
+                }
             }
         };
-        let source_reader = BufReader::new(source);
+        let mut source_reader = BufReader::new(source);
+        let gzipped = file.ends_with(".gz") || source_reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+        let format_path = if gzipped {
+            file.strip_suffix(".gz").unwrap_or(&file)
+        } else {
+            &file
+        };
+        let source_reader: Box<dyn Read> = if gzipped {
+            Box::new(MultiGzDecoder::new(source_reader))
+        } else {
+            Box::new(source_reader)
+        };
+        let source_reader = Utf8Reader::new(source_reader, file.clone(), self.opts.lenient);
 
         debug!("converting {} to nt format", &file);
 
-        let mut serializer = RdfSerializer::from_format(NTriples).for_writer(dest_writer.by_ref());
-        let v = std::time::Instant::now();
-        let rdf_format = if let Some(t) =
-            RdfFormat::from_extension(Path::new(&file).extension().unwrap().to_str().unwrap())
+        let rdf_format = if let Some(format) = in_memory_format {
+            format
+        } else if is_stdin {
+            match self.opts.input_format {
+                Some(t) => t,
+                None => {
+                    error!("no format provided for stdin");
+                    return Err(Rdf2HdtError::UnsupportedFormat(
+                        "a format is required when reading RDF from stdin".to_string(),
+                    ));
+                }
+            }
+        } else if let Some(format) = self.opts.file_formats.get(&file) {
+            *format
+        } else if let Some(format) = self.opts.input_format {
+            format
+        } else if let Some(t) = Path::new(format_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(format_from_extension)
         {
             t
         } else {
             error!("unrecognized file extension for {file}");
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("unrecognized file extension for {file}"),
-            )
-            .into());
-        };
-        let quads = RdfParser::from_format(rdf_format)
-            .with_base_iri(format!(
-                "file://{}",
-                Path::new(&file).file_name().unwrap().to_str().unwrap()
-            ))?
-            .for_reader(source_reader);
-        let mut warned = false;
-        for q in quads {
-            let q = match q {
-                Ok(v) => v,
-                Err(e) => {
-                    match e {
-                        RdfParseError::Io(v) => {
-                            // I/O error while reading file
-                            error!("Error reading file {file}: {v}");
-                            return Err(v.into());
+            return Err(Rdf2HdtError::UnsupportedFormat(format!(
+                "unrecognized file extension for {file}"
+            )));
+        };
+        let base_iri = match &self.opts.base_iri {
+            Some(base_iri) => Some(base_iri.clone()),
+            // a path such as `..` has no file name to resolve against
+            None if !is_stdin && in_memory_format.is_none() => Path::new(&file)
+                .file_name()
+                .map(|name| format!("file://{}", name.to_string_lossy())),
+            None => None,
+        };
+        let warnings = Rc::new(RefCell::new(Vec::new()));
+        let invalid_base = |e| Rdf2HdtError::InvalidInput(format!("invalid base IRI: {e}"));
+        let jsonld = matches!(rdf_format, RdfFormat::JsonLd { .. });
+        if jsonld {
+            self.jsonld_bnodes = CounterCanonicalizer::new(format!("j{}_", self.next_index));
+        }
+        let bnode_scope = (!jsonld && self.scope_bnodes && !self.opts.shared_bnode_scope)
+            .then(|| format!("f{}_", self.next_index));
+        self.next_index += 1;
+        let quads: Box<dyn Iterator<Item = _>> = if jsonld && self.opts.resolve_remote_contexts {
+            remote_jsonld_quads(Box::new(source_reader), base_iri)?
+        } else if rdf_format == RdfFormat::N3 {
+            // oxrdfio fails on generalized triples with a message that can't be
+            // told apart from syntax errors, use the N3 parser directly
+            let mut parser = N3Parser::new();
+            if let Some(base_iri) = base_iri {
+                parser = parser.with_base_iri(base_iri).map_err(invalid_base)?;
+            }
+            let scanner = PrefixScanner::new(
+                parser.low_level(),
+                BufReader::new(source_reader),
+                file.clone(),
+                warnings.clone(),
+            );
+            Box::new(scanner.map(|q| n3_to_quad(q.map_err(RdfParseError::from)?)))
+        } else if rdf_format == RdfFormat::Turtle {
+            // the prefix scanner feeds the parser itself, to look at its bindings
+            let mut parser = TurtleParser::new();
+            if let Some(base_iri) = base_iri {
+                parser = parser.with_base_iri(base_iri).map_err(invalid_base)?;
+            }
+            let scanner = PrefixScanner::new(
+                parser.low_level(),
+                BufReader::new(source_reader),
+                file.clone(),
+                warnings.clone(),
+            );
+            Box::new(scanner.map(|t| Ok(t?.in_graph(GraphName::DefaultGraph))))
+        } else if rdf_format == RdfFormat::TriG {
+            let mut parser = TriGParser::new();
+            if let Some(base_iri) = base_iri {
+                parser = parser.with_base_iri(base_iri).map_err(invalid_base)?;
+            }
+            let scanner = PrefixScanner::new(
+                parser.low_level(),
+                BufReader::new(source_reader),
+                file.clone(),
+                warnings.clone(),
+            );
+            Box::new(scanner.map(|q| Ok(q?)))
+        } else {
+            let mut parser = RdfParser::from_format(rdf_format);
+            if let Some(base_iri) = base_iri {
+                parser = parser.with_base_iri(base_iri).map_err(invalid_base)?;
+            }
+            Box::new(parser.for_reader(source_reader))
+        };
+        Ok(CurrentFile {
+            name: file,
+            quads,
+            gzipped,
+            jsonld,
+            bnode_scope,
+            warned: false,
+            unknown_datatypes: HashSet::new(),
+            warnings,
+            timer: std::time::Instant::now(),
+        })
+    }
+}
+
+impl RdfReader for FileReader<'_> {
+    fn next_triple(&mut self) -> Option<Result<Triple, Rdf2HdtError>> {
+        if let Err(e) = self.opts.check_cancelled() {
+            return Some(Err(e));
+        }
+        if self
+            .opts
+            .max_triples
+            .is_some_and(|max| self.returned >= max)
+        {
+            if let Some(current) = self.current.take() {
+                current.close(&mut self.stats);
+                debug!(
+                    "stopping in {} after {} triples",
+                    current.name, self.returned
+                );
+            }
+            return None;
+        }
+        loop {
+            let triple = self.read_triple();
+            if let Some(Ok(t)) = &triple {
+                let predicate = t.predicate.as_str();
+                if self.excluded.contains(predicate) {
+                    self.stats.excluded += 1;
+                    self.stats.drop_terms(t);
+                    continue;
+                }
+                if self
+                    .included
+                    .as_ref()
+                    .is_some_and(|p| !p.contains(predicate))
+                {
+                    self.stats.not_included += 1;
+                    self.stats.drop_terms(t);
+                    continue;
+                }
+                if self
+                    .opts
+                    .triple_filter
+                    .as_ref()
+                    .is_some_and(|keep| !keep(t))
+                {
+                    self.stats.filtered += 1;
+                    self.stats.drop_terms(t);
+                    continue;
+                }
+                if let Err(e) = self.count_terms(t) {
+                    return Some(Err(e));
+                }
+                self.returned += 1;
+            }
+            return triple;
+        }
+    }
+}
+
+impl FileReader<'_> {
+    fn count_terms(&mut self, triple: &Triple) -> Result<(), Rdf2HdtError> {
+        let Some(max) = self.opts.max_distinct_terms else {
+            return Ok(());
+        };
+        self.terms.extend(term_strings(triple));
+        let count = self.terms.len() as u64;
+        if count > max {
+            let file = self
+                .current
+                .as_ref()
+                .map_or("the input", |c| c.name.as_str());
+            error!(
+                "{count} distinct terms after {} triples of {file}",
+                self.returned
+            );
+            return Err(Rdf2HdtError::LimitExceeded(format!(
+                "{count} distinct terms reached in {file}, more than the maximum of {max}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn read_triple(&mut self) -> Option<Result<Triple, Rdf2HdtError>> {
+        if let Some(triple) = self.pending.pop_front() {
+            return Some(Ok(triple));
+        }
+        loop {
+            if self.current.is_none() {
+                let file = self.files.next()?;
+                match self.open(file) {
+                    Ok(c) => self.current = Some(c),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            let current = self.current.as_mut()?;
+            let file = &current.name;
+            match current.quads.next() {
+                Some(Ok(q)) => {
+                    if !current.warned && q.graph_name != oxrdf::GraphName::DefaultGraph {
+                        current.warned = true;
+                        warn!("HDT does not support named graphs, merging triples for {file}");
+                    }
+                    let mut triple = Triple::new(q.subject, q.predicate, q.object);
+                    if let Some(scope) = &current.bnode_scope {
+                        scope_bnodes(&mut triple, scope);
+                    }
+                    if self.opts.canonical_bnodes {
+                        // before reifying, so reification nodes are hashed from stable labels
+                        self.bnodes.canonicalize(&mut triple);
+                    } else if current.jsonld {
+                        self.jsonld_bnodes.canonicalize(&mut triple);
+                    }
+                    normalize_literals(&mut triple, self.opts);
+                    if self.opts.normalize_iris || self.opts.canonical_trailing_slash {
+                        normalize_iris(&mut triple, self.opts);
+                    }
+                    if has_nul(&triple) {
+                        if self.opts.lenient {
+                            warn!(
+                                "skipping statement with a NUL character in {file}: {}",
+                                log_triple(&triple, self.opts)
+                            );
+                            self.stats.skipped += 1;
+                            continue;
                         }
-                        RdfParseError::Syntax(syn_err) => {
-                            error!("syntax error for RDF file {file}: {syn_err}");
-                            return Err(syn_err.into());
+                        error!("literal with a NUL character in {file}");
+                        return Some(Err(nul_error(file)));
+                    }
+                    if let Term::Triple(quoted) = &triple.object {
+                        match self.opts.rdf_star {
+                            RdfStarMode::Reify => {
+                                triple.object = reify(quoted, &mut self.pending).into();
+                            }
+                            RdfStarMode::Reject if self.opts.lenient => {
+                                warn!(
+                                    "skipping statement with a quoted triple in {file}: {}",
+                                    log_triple(&triple, self.opts)
+                                );
+                                self.stats.skipped += 1;
+                                continue;
+                            }
+                            RdfStarMode::Reject => {
+                                error!(
+                                    "quoted triple in {file}: {}",
+                                    log_triple(&triple, self.opts)
+                                );
+                                return Some(Err(quoted_triple_error(file)));
+                            }
                         }
                     }
+                    if let Term::Literal(literal) = &triple.object {
+                        let datatype = literal.datatype().as_str();
+                        if is_unknown_datatype(datatype)
+                            && current.unknown_datatypes.insert(datatype.to_string())
+                        {
+                            let warning = format!(
+                                "{file} has literals of <{datatype}>, which isn't an RDF or XSD \
+                                 datatype"
+                            );
+                            warn!("{warning}");
+                            self.stats.warnings.push(warning);
+                        }
+                    }
+                    return Some(Ok(triple));
                 }
-            };
-            if !warned && q.graph_name != oxrdf::GraphName::DefaultGraph {
-                warned = true;
-                warn!("HDT does not support named graphs, merging triples for {file}");
+                Some(Err(RdfParseError::Io(v)))
+                    if current.gzipped && v.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    error!("truncated gzip stream in {file}: {v}");
+                    return Some(Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        format!("gzip stream in {file} ended unexpectedly, the file is truncated"),
+                    )
+                    .into()));
+                }
+                Some(Err(RdfParseError::Io(v))) => {
+                    if let Some(generalized) = GeneralizedTriple::from_io_error(&v) {
+                        if self.opts.generalized_rdf == GeneralizedRdfMode::Skip
+                            || self.opts.lenient
+                        {
+                            warn!("skipping {generalized} in {file}");
+                            self.stats.skipped += 1;
+                            continue;
+                        }
+                        error!("{generalized} in {file}");
+                        return Some(Err(Rdf2HdtError::InvalidInput(format!(
+                            "{file} contains a {generalized}, which HDT cannot encode; skip \
+                             it with GeneralizedRdfMode::Skip"
+                        ))));
+                    }
+                    if let Some(invalid) = InvalidUtf8::from_io_error(&v) {
+                        error!("{invalid} in {file}");
+                        return Some(Err(Rdf2HdtError::Parse {
+                            line: invalid.line,
+                            column: invalid.column,
+                            msg: format!("{invalid} in {file}"),
+                        }));
+                    }
+                    // I/O error while reading file
+                    error!("Error reading file {file}: {v}");
+                    return Some(Err(v.into()));
+                }
+                Some(Err(RdfParseError::Syntax(syn_err))) if self.opts.lenient => {
+                    let (line, column) = position(&syn_err);
+                    warn!("skipping invalid statement in {file} at {line}:{column}: {syn_err}");
+                    self.stats.skipped += 1;
+                }
+                Some(Err(RdfParseError::Syntax(syn_err))) => {
+                    error!("syntax error for RDF file {file}: {syn_err}");
+                    return Some(Err(RdfParseError::Syntax(syn_err).into()));
+                }
+                None => {
+                    debug!("RDF to NTriple convert time: {:?}", current.timer.elapsed());
+                    current.close(&mut self.stats);
+                    self.current = None;
+                }
+            }
+        }
+    }
+}
+
+/// Serializes every triple of `reader` to `output` as N-Triples, returning the
+/// number of triples written.
+pub(crate) fn write_nt(
+    reader: &mut impl RdfReader,
+    output: impl Write,
+    opts: &Options,
+) -> Result<u64, Rdf2HdtError> {
+    let mut count: u64 = 0;
+    serialize_nt(reader, BufWriter::new(output), || {
+        count += 1;
+        if count.is_multiple_of(PROGRESS_INTERVAL) {
+            opts.report_progress(BuildPhase::Parsing, count);
+        }
+    })?;
+    opts.report_progress(BuildPhase::Parsing, count);
+    Ok(count)
+}
+
+/// What [`scan_nt_file`] found in a valid N-Triples file.
+pub(crate) struct NtScan {
+    /// Number of triples.
+    pub(crate) triples: u64,
+    /// Whether the file starts with a byte order mark, has CRLF line breaks or
+    /// writes out the `xsd:string` datatype of a literal, which the hdt crate
+    /// doesn't read the way rdf2hdt does, so it must go through [`FileReader`].
+    pub(crate) needs_reader: bool,
+}
+
+/// Datatype of a literal that is the same as no datatype.
+const XSD_STRING_DATATYPE: &[u8] = b"^^<http://www.w3.org/2001/XMLSchema#string>";
+
+/// Passes bytes through, noting whether they have a CRLF line break or
+/// [`XSD_STRING_DATATYPE`].
+struct NtBytesScanner<R> {
+    inner: R,
+    crlf: bool,
+    xsd_string: bool,
+    /// Last bytes read, to find the sequences cut by the end of a chunk.
+    tail: Vec<u8>,
+}
+
+impl<R: Read> Read for NtBytesScanner<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if !(self.crlf && self.xsd_string) && n > 0 {
+            let chunk = &buf[..n];
+            let keep = XSD_STRING_DATATYPE.len() - 1;
+            self.tail.extend_from_slice(&chunk[..n.min(keep)]);
+            for bytes in [&self.tail[..], chunk] {
+                self.crlf |= contains(bytes, b"\r\n");
+                self.xsd_string |= contains(bytes, XSD_STRING_DATATYPE);
+            }
+            if n >= keep {
+                self.tail.clear();
+                self.tail.extend_from_slice(&chunk[n - keep..]);
+            } else {
+                self.tail.drain(..self.tail.len().saturating_sub(keep));
             }
-            serializer.serialize_triple(oxrdf::TripleRef {
-                subject: q.subject.as_ref(),
-                predicate: q.predicate.as_ref(),
-                object: q.object.as_ref(),
-            })?
         }
+        Ok(n)
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Parses the N-Triples file at `path`, counting its triples and looking for
+/// what the hdt crate doesn't read the way rdf2hdt does, or returns the first
+/// invalid triple as an error with its line and byte offset.
+///
+/// A single `.nt` input is checked with this before it is handed to the hdt
+/// crate as is, which doesn't validate IRIs. Literals with a NUL character are
+/// rejected as [`FileReader`] does, and so are quoted triples, as with
+/// [`RdfStarMode::Reject`], the only mode under which the input is handed over.
+pub(crate) fn scan_nt_file(path: &Path) -> Result<NtScan, Rdf2HdtError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let bom = reader.fill_buf()?.starts_with(UTF8_BOM);
+    if bom {
+        reader.consume(UTF8_BOM.len());
+    }
+    let mut reader = NtBytesScanner {
+        inner: reader,
+        crlf: false,
+        xsd_string: false,
+        tail: Vec::new(),
+    };
+    let mut triples: u64 = 0;
+    for quad in RdfParser::from_format(NTriples).for_reader(&mut reader) {
+        match quad {
+            Ok(quad) if matches!(quad.object, Term::Triple(_)) => {
+                return Err(quoted_triple_error(&path.display().to_string()));
+            }
+            Ok(quad) if matches!(&quad.object, Term::Literal(l) if l.value().contains('\0')) => {
+                return Err(nul_error(&path.display().to_string()));
+            }
+            Ok(_) => triples += 1,
+            Err(RdfParseError::Syntax(e)) => {
+                let (line, column) = position(&e);
+                let offset = e.location().map_or(0, |location| location.start.offset);
+                return Err(Rdf2HdtError::Parse {
+                    line,
+                    column,
+                    msg: format!("{} at byte {offset}: {e}", path.display()),
+                });
+            }
+            Err(RdfParseError::Io(e)) => return Err(e.into()),
+        }
+    }
+    Ok(NtScan {
+        triples,
+        needs_reader: bom || reader.crlf || reader.xsd_string,
+    })
+}
+
+/// Returns the first invalid triple of the N-Triples file at `path` as an error,
+/// or `None` if the file is valid, see [`scan_nt_file`].
+///
+/// The hdt crate's errors don't say where its N-Triples parser failed, so this
+/// is used to find the offending line once it has.
+pub(crate) fn locate_nt_error(path: &Path) -> Result<Option<Rdf2HdtError>, Rdf2HdtError> {
+    match scan_nt_file(path) {
+        Ok(_) => Ok(None),
+        Err(e @ (Rdf2HdtError::Parse { .. } | Rdf2HdtError::InvalidInput(_))) => Ok(Some(e)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Serializes the triples of the `file_paths` to `output` as N-Triples,
+/// returning the number of triples written and the counts kept while parsing.
+///
+/// Several files are parsed in parallel, on up to [`Options::parser_threads`]
+/// threads, into intermediate files that are then concatenated. Files are read
+/// one after the other with [`Options::canonical_bnodes`] though, since canonical
+/// labels are assigned in input order, and with [`Options::max_triples`] and
+/// [`Options::max_distinct_terms`].
+pub(crate) fn write_files_nt(
+    mut file_paths: Vec<String>,
+    output: impl Write,
+    opts: &Options,
+) -> Result<(u64, ReadStats), Rdf2HdtError> {
+    // blank node labels depend on the order files are read, not on the order given
+    file_paths.sort_unstable();
+    if file_paths.len() < 2
+        || opts.parser_threads == Some(1)
+        || opts.canonical_bnodes
+        || opts.max_triples.is_some()
+        || opts.max_distinct_terms.is_some()
+    {
+        let mut reader = FileReader::new(file_paths, opts);
+        let count = write_nt(&mut reader, output, opts)?;
+        return Ok((count, reader.stats));
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(opts.parser_threads.unwrap_or(0))
+        .build()
+        .map_err(std::io::Error::other)?;
+    // a few chunks per thread, so threads done early pick up the remaining files
+    let chunk_size = file_paths.len().div_ceil(pool.current_num_threads() * 4);
+    let count = AtomicU64::new(0);
+    let parts = pool.install(|| {
+        file_paths
+            .par_chunks(chunk_size)
+            .enumerate()
+            .map(|(chunk, files)| {
+                let part = opts.temp_file(".nt")?;
+                let mut reader =
+                    FileReader::new(files.to_vec(), opts).with_first_index(chunk * chunk_size);
+                serialize_nt(&mut reader, BufWriter::new(part.reopen()?), || {
+                    let count = count.fetch_add(1, Ordering::Relaxed) + 1;
+                    if count.is_multiple_of(PROGRESS_INTERVAL) {
+                        opts.report_progress(BuildPhase::Parsing, count);
+                    }
+                })?;
+                Ok((part, reader.stats))
+            })
+            .collect::<Result<Vec<_>, Rdf2HdtError>>()
+    })?;
 
-        serializer.finish()?;
-        debug!("RDF to NTriple convert time: {:?}", v.elapsed());
+    let mut dest_writer = BufWriter::new(output);
+    let mut stats = ReadStats::default();
+    for (part, part_stats) in parts {
+        std::io::copy(&mut part.reopen()?, &mut dest_writer)?;
+        stats.skipped += part_stats.skipped;
+        stats.excluded += part_stats.excluded;
+        stats.not_included += part_stats.not_included;
+        stats.filtered += part_stats.filtered;
+        stats.dropped_terms.extend(part_stats.dropped_terms);
+        stats.warnings.extend(part_stats.warnings);
     }
     dest_writer.flush()?;
+    let count = count.into_inner();
+    opts.report_progress(BuildPhase::Parsing, count);
+    Ok((count, stats))
+}
+
+/// Serializes every triple of `reader` to `writer`, calling `on_triple` after each one.
+fn serialize_nt(
+    reader: &mut impl RdfReader,
+    writer: impl Write,
+    mut on_triple: impl FnMut(),
+) -> Result<(), Rdf2HdtError> {
+    let mut serializer = RdfSerializer::from_format(NTriples).for_writer(writer);
+    while let Some(t) = reader.next_triple() {
+        serializer.serialize_triple(&t?)?;
+        on_triple();
+    }
+    serializer.finish()?.flush()?;
     Ok(())
 }
 
@@ -92,13 +1155,22 @@ mod tests {
 
     use super::*;
 
+    fn convert_to_nt(
+        file_paths: Vec<String>,
+        output_file: std::fs::File,
+        opts: &Options,
+    ) -> Result<u64, Rdf2HdtError> {
+        write_nt(&mut FileReader::new(file_paths, opts), output_file, opts)
+    }
+
     #[test]
     fn test_rdf() {
         let tmp_file = tempfile::Builder::new().suffix(".nt").tempfile().expect("");
         assert!(
             (convert_to_nt(
                 vec!["tests/resources/apple.ttl".to_string()],
-                tmp_file.reopen().expect("error opening tmp file")
+                tmp_file.reopen().expect("error opening tmp file"),
+                &Options::default()
             ))
             .is_ok()
         );
@@ -110,4 +1182,726 @@ mod tests {
         assert!(quads.is_ok());
         assert_eq!(quads.unwrap().len(), 9)
     }
+
+    #[test]
+    fn test_file_reader_streams_triples() {
+        let opts = Options::default();
+        let mut reader = FileReader::new(
+            vec![
+                "tests/resources/apple.ttl".to_string(),
+                "tests/resources/apple.ttl".to_string(),
+            ],
+            &opts,
+        );
+        let mut count = 0;
+        while let Some(t) = reader.next_triple() {
+            assert!(t.is_ok());
+            count += 1;
+        }
+        assert_eq!(count, 18);
+    }
+
+    #[test]
+    fn test_parse_error_line() {
+        let tmp_file = tempfile::Builder::new().suffix(".nt").tempfile().expect("");
+        let res = convert_to_nt(
+            vec!["tests/resources/invalid.ttl".to_string()],
+            tmp_file.reopen().expect("error opening tmp file"),
+            &Options::default(),
+        );
+        assert!(matches!(res, Err(Rdf2HdtError::Parse { line: 3, .. })));
+    }
+
+    #[test]
+    fn test_lenient() {
+        let file = vec!["tests/resources/partially_invalid.nt".to_string()];
+        let tmp_file = tempfile::Builder::new().suffix(".nt").tempfile().expect("");
+        assert!(
+            convert_to_nt(
+                file.clone(),
+                tmp_file.reopen().expect("error opening tmp file"),
+                &Options::default(),
+            )
+            .is_err()
+        );
+
+        let opts = Options {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut reader = FileReader::new(file, &opts);
+        let count = write_nt(
+            &mut reader,
+            tmp_file.reopen().expect("error opening tmp file"),
+            &opts,
+        );
+        assert_eq!(count.expect("lenient conversion failed"), 2);
+        assert!(reader.stats.skipped > 0);
+    }
+
+    #[test]
+    fn test_max_triples() -> Result<(), Rdf2HdtError> {
+        let opts = Options {
+            max_triples: Some(4),
+            ..Default::default()
+        };
+        let tmp_file = tempfile::Builder::new().suffix(".nt").tempfile()?;
+        let files = vec![
+            "tests/resources/bnodes_a.ttl".to_string(),
+            "tests/resources/bnodes_b.ttl".to_string(),
+        ];
+        let (count, _) = write_files_nt(files, tmp_file.reopen()?, &opts)?;
+        assert_eq!(count, 4);
+        let nt = std::fs::read_to_string(tmp_file.path())?;
+        assert_eq!(nt.lines().count(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_distinct_terms() {
+        let read = |max| {
+            let opts = Options {
+                max_distinct_terms: Some(max),
+                ..Default::default()
+            };
+            let mut reader = FileReader::new(vec!["tests/resources/apple.ttl".to_string()], &opts);
+            let mut count = 0;
+            while let Some(t) = reader.next_triple() {
+                if let Err(e) = t {
+                    return Err((count, e));
+                }
+                count += 1;
+            }
+            Ok(count)
+        };
+        // the 9 triples have 17 distinct terms, rdf:type and ex:Fruit appear twice
+        assert!(matches!(read(17), Ok(9)));
+        // the fifth triple brings the count to 11
+        match read(10) {
+            Err((4, Rdf2HdtError::LimitExceeded(msg))) => {
+                assert!(msg.starts_with("11 distinct terms"), "{msg}")
+            }
+            other => panic!("expected LimitExceeded, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_bnode_scope() {
+        let subjects = |opts: &Options| {
+            let files = vec![
+                "tests/resources/bnode_scope_a.ttl".to_string(),
+                "tests/resources/bnode_scope_b.ttl".to_string(),
+            ];
+            let mut reader = FileReader::new(files, opts);
+            let mut subjects = std::collections::HashSet::new();
+            while let Some(t) = reader.next_triple() {
+                subjects.insert(t.expect("error parsing triple").subject);
+            }
+            subjects.len()
+        };
+        // both files describe a different _:b0
+        assert_eq!(subjects(&Options::default()), 2);
+        let opts = Options {
+            canonical_bnodes: true,
+            ..Default::default()
+        };
+        assert_eq!(subjects(&opts), 2);
+        let opts = Options {
+            shared_bnode_scope: true,
+            ..Default::default()
+        };
+        assert_eq!(subjects(&opts), 1);
+
+        // a single file keeps its labels, as when the hdt crate reads it
+        let opts = Options::default();
+        let mut reader =
+            FileReader::new(vec!["tests/resources/bnode_scope_a.ttl".to_string()], &opts);
+        let triple = reader.next_triple().unwrap().expect("error parsing triple");
+        assert_eq!(triple.subject, BlankNode::new_unchecked("b0").into());
+    }
+
+    #[test]
+    fn test_canonical_bnodes() {
+        let opts = Options {
+            canonical_bnodes: true,
+            ..Default::default()
+        };
+        let read = |file: &str| {
+            let mut reader = FileReader::new(vec![file.to_string()], &opts);
+            let mut triples = Vec::new();
+            while let Some(t) = reader.next_triple() {
+                triples.push(t.expect("error parsing triple"));
+            }
+            triples
+        };
+        let a = read("tests/resources/bnodes_a.ttl");
+        let b = read("tests/resources/bnodes_b.ttl");
+        assert_eq!(a.len(), 3);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_locate_nt_error() -> Result<(), Rdf2HdtError> {
+        let tmp_nt = tempfile::Builder::new().suffix(".nt").tempfile()?;
+        std::fs::write(
+            tmp_nt.path(),
+            "<http://example.org/s> <http://example.org/p> \"a\" .\n\
+             <http://example.org/s> <http://example.org/p> \"b\" .\n\
+             <http://example.org/s> <http://example.org/p> \"c .\n",
+        )?;
+        match locate_nt_error(tmp_nt.path())? {
+            Some(Rdf2HdtError::Parse { line: 3, msg, .. }) => {
+                assert!(msg.contains(" at byte "), "{msg}")
+            }
+            other => panic!("expected a parse error on line 3, got {other:?}"),
+        }
+        assert!(locate_nt_error(Path::new("tests/resources/order.nt"))?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_datatype() {
+        let opts = Options::default();
+        let ttl = "@prefix xsd: <http://www.w3.org/2001/XMLSchema#>.\n\
+                   <http://ex/s> <http://ex/p> \"1\"^^xsd:integr, \"2\"^^xsd:integr, \"3\"^^xsd:integer, \
+                   \"4\"^^<http://ex/integr>.";
+        let mut reader = FileReader::from_bytes(ttl.as_bytes().to_vec(), RdfFormat::Turtle, &opts);
+        let triples = std::iter::from_fn(|| reader.next_triple()).count();
+        assert_eq!(triples, 4);
+        assert_eq!(reader.stats.warnings.len(), 1);
+        assert!(
+            reader.stats.warnings[0].contains("XMLSchema#integr>"),
+            "{:?}",
+            reader.stats.warnings
+        );
+    }
+
+    #[test]
+    fn test_log_triple() {
+        let opts = Options {
+            log_prefixes: vec![("ex".to_string(), "http://example.org/".to_string())],
+            ..Default::default()
+        };
+        let ex = |name| NamedNode::new_unchecked(format!("http://example.org/{name}"));
+        let quoted = Triple::new(
+            ex("a"),
+            NamedNode::new_unchecked("http://xmlns.com/foaf/0.1/knows"),
+            BlankNode::new_unchecked("b"),
+        );
+        let triple = Triple::new(ex("c"), ex("says"), quoted);
+        assert_eq!(
+            log_triple(&triple, &opts),
+            "ex:c ex:says << ex:a foaf:knows _:b . >> ."
+        );
+        let triple = Triple::new(
+            NamedNode::new_unchecked("http://other.org/s"),
+            ex("p"),
+            Literal::new_typed_literal("1", ex("int")),
+        );
+        assert_eq!(
+            log_triple(&triple, &opts),
+            "<http://other.org/s> ex:p \"1\"^^ex:int ."
+        );
+    }
+
+    #[test]
+    fn test_input_format() -> Result<(), Rdf2HdtError> {
+        let tmp_file = tempfile::Builder::new().suffix(".txt").tempfile()?;
+        std::fs::copy("tests/resources/apple.ttl", tmp_file.path())?;
+        let files = vec![tmp_file.path().to_str().unwrap().to_string()];
+        let res = write_files_nt(files.clone(), std::io::sink(), &Options::default());
+        assert!(matches!(res, Err(Rdf2HdtError::UnsupportedFormat(_))));
+
+        let opts = Options {
+            input_format: Some(RdfFormat::Turtle),
+            ..Default::default()
+        };
+        let (count, _) = write_files_nt(files, std::io::sink(), &opts)?;
+        assert_eq!(count, 9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_formats() -> Result<(), Rdf2HdtError> {
+        let tmp_file = tempfile::Builder::new().suffix(".txt").tempfile()?;
+        std::fs::copy("tests/resources/apple.ttl", tmp_file.path())?;
+        let txt = tmp_file.path().to_str().unwrap().to_string();
+        let files = vec![txt.clone(), "tests/resources/order.nt".to_string()];
+        let opts = Options {
+            file_formats: std::collections::HashMap::from([(txt, RdfFormat::Turtle)]),
+            ..Default::default()
+        };
+        let (count, _) = write_files_nt(files.clone(), std::io::sink(), &opts)?;
+        let (order_count, _) = write_files_nt(
+            vec!["tests/resources/order.nt".to_string()],
+            std::io::sink(),
+            &Options::default(),
+        )?;
+        assert_eq!(count, 9 + order_count);
+
+        // the listed format wins over Options::input_format
+        let opts = Options {
+            input_format: Some(RdfFormat::NTriples),
+            ..opts
+        };
+        let (count, _) = write_files_nt(files, std::io::sink(), &opts)?;
+        assert_eq!(count, 9 + order_count);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nquads_graphs() {
+        let opts = Options::default();
+        let files = vec![
+            "tests/resources/graphs.nq".to_string(),
+            "tests/resources/graphs_b.nq".to_string(),
+        ];
+        let mut reader = FileReader::new(files, &opts);
+        let triples: Vec<_> = std::iter::from_fn(|| reader.next_triple())
+            .map(|t| t.expect("error parsing triple"))
+            .collect();
+        // default graph, IRI and blank node graph labels are all merged
+        let objects: Vec<_> = triples.iter().map(|t| t.object.to_string()).collect();
+        assert_eq!(
+            objects,
+            [
+                "\"default\"",
+                "\"iri\"",
+                "\"bnode\"",
+                "\"graph node\"",
+                "\"other file\""
+            ]
+        );
+        // _:g of each file stays a distinct node
+        assert_eq!(triples[3].subject, BlankNode::new_unchecked("f0_g").into());
+        assert_eq!(triples[4].subject, BlankNode::new_unchecked("f1_g").into());
+    }
+
+    #[test]
+    fn test_custom_canonicalizer() {
+        /// Labels nodes after the input label, which is only unique per file.
+        struct Prefixing;
+        impl BnodeCanonicalizer for Prefixing {
+            fn relabel(&mut self, node: &BlankNode) -> BlankNode {
+                BlankNode::new_unchecked(format!("custom_{}", node.as_str()))
+            }
+        }
+        let opts = Options {
+            canonical_bnodes: true,
+            bnode_canonicalizer: Some(Box::new(|| Box::new(Prefixing))),
+            ..Default::default()
+        };
+        let mut reader = FileReader::new(vec!["tests/resources/bnodes_a.ttl".to_string()], &opts);
+        let mut bnodes = 0;
+        while let Some(t) = reader.next_triple() {
+            if let NamedOrBlankNode::BlankNode(b) = t.expect("error parsing triple").subject {
+                assert!(["custom_x", "custom_y"].contains(&b.as_str()), "{b}");
+                bnodes += 1;
+            }
+        }
+        assert!(bnodes > 0);
+    }
+
+    #[test]
+    fn test_hdt_term() -> Result<(), Rdf2HdtError> {
+        assert_eq!(
+            hdt_term("http://example.org/a")?,
+            Term::from(NamedNode::new_unchecked("http://example.org/a"))
+        );
+        assert_eq!(
+            hdt_term("_:b0")?,
+            Term::from(BlankNode::new_unchecked("b0"))
+        );
+        assert_eq!(
+            hdt_term("\"say \"hi\"\"@en")?,
+            Term::from(Literal::new_language_tagged_literal_unchecked(
+                "say \"hi\"",
+                "en"
+            ))
+        );
+        assert_eq!(
+            hdt_term("\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>")?,
+            Term::from(Literal::new_typed_literal("1", oxrdf::vocab::xsd::INTEGER))
+        );
+        assert!(hdt_term("\"unterminated").is_err());
+        for term in [
+            "http://example.org/a",
+            "_:b0",
+            "\"plain\"",
+            "\"say \"hi\"\"@en",
+            "\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+        ] {
+            assert_eq!(hdt_string(&hdt_term(term)?), term);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rdf_xml() {
+        let opts = Options::default();
+        let mut reader = FileReader::new(vec!["tests/resources/people.rdf".to_string()], &opts);
+        let mut triples = Vec::new();
+        while let Some(t) = reader.next_triple() {
+            triples.push(t.expect("error parsing triple").to_string());
+        }
+        assert_eq!(triples.len(), 6);
+        // rdf:ID and relative rdf:about/rdf:resource resolve against the file's base IRI
+        let alice = "<file://people.rdf#alice>";
+        let bob = "<file://people.rdf#bob>";
+        assert!(triples.contains(&format!("{alice} <http://xmlns.com/foaf/0.1/knows> {bob}")));
+        assert!(triples.contains(&format!(
+            "{bob} <http://xmlns.com/foaf/0.1/name> \"Bob\"@en"
+        )));
+        // xml:base overrides the file's base IRI
+        assert!(triples.contains(
+            &"<http://example.org/doc> <http://purl.org/dc/elements/1.1/creator> <http://example.org/people/carol>"
+                .to_string()
+        ));
+
+        for ext in ["rdf", "owl", "xml", "OWL"] {
+            assert_eq!(format_from_extension(ext), Some(RdfFormat::RdfXml));
+        }
+    }
+
+    #[test]
+    fn test_jsonld() {
+        let opts = Options::default();
+        let read = |file: &str| {
+            let mut reader = FileReader::new(vec![file.to_string()], &opts);
+            let mut triples = Vec::new();
+            while let Some(t) = reader.next_triple() {
+                triples.push(t.expect("error parsing triple").to_string());
+            }
+            triples
+        };
+        let triples = read("tests/resources/people.jsonld");
+        assert_eq!(triples.len(), 5);
+        assert!(triples.contains(
+            &"<http://example.org/alice> <http://xmlns.com/foaf/0.1/name> \"Alice\"".to_string()
+        ));
+        // the address is an unlabeled node, relabeled the same way on every build
+        assert!(triples.contains(
+            &"<http://example.org/alice> <http://schema.org/address> _:j0_0".to_string()
+        ));
+        assert_eq!(triples, read("tests/resources/people.jsonld"));
+    }
+
+    #[test]
+    fn test_jsonld_remote_context_disabled() {
+        let opts = Options::default();
+        let mut reader = FileReader::new(
+            vec!["tests/resources/remote_context.jsonld".to_string()],
+            &opts,
+        );
+        assert!(std::iter::from_fn(|| reader.next_triple()).any(|t| t.is_err()));
+    }
+
+    #[cfg(not(feature = "remote-contexts"))]
+    #[test]
+    fn test_jsonld_remote_context_feature() {
+        let opts = Options {
+            resolve_remote_contexts: true,
+            ..Default::default()
+        };
+        let mut reader = FileReader::new(
+            vec!["tests/resources/remote_context.jsonld".to_string()],
+            &opts,
+        );
+        assert!(matches!(
+            reader.next_triple(),
+            Some(Err(Rdf2HdtError::InvalidInput(_)))
+        ));
+    }
+
+    #[test]
+    fn test_parallel_files() -> Result<(), Rdf2HdtError> {
+        let files: Vec<String> = [
+            "apple.ttl",
+            "duplicates.ttl",
+            "people.rdf",
+            "people.jsonld",
+            "bnodes_a.ttl",
+        ]
+        .iter()
+        .map(|f| format!("tests/resources/{f}"))
+        .collect();
+        let nt_lines = |threads| -> Result<Vec<String>, Rdf2HdtError> {
+            let opts = Options {
+                parser_threads: Some(threads),
+                ..Default::default()
+            };
+            let tmp_file = tempfile::Builder::new().suffix(".nt").tempfile()?;
+            let (count, _) = write_files_nt(files.clone(), tmp_file.reopen()?, &opts)?;
+            let mut lines: Vec<_> = std::fs::read_to_string(tmp_file.path())?
+                .lines()
+                .map(str::to_string)
+                .collect();
+            assert_eq!(lines.len() as u64, count);
+            lines.sort();
+            Ok(lines)
+        };
+        assert_eq!(nt_lines(1)?, nt_lines(3)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_base_iri() {
+        let opts = Options {
+            base_iri: Some("http://example.org/base/".to_string()),
+            ..Default::default()
+        };
+        let read = |file: &str| {
+            let mut reader = FileReader::new(vec![file.to_string()], &opts);
+            let t = reader.next_triple().unwrap().expect("error parsing triple");
+            (t.subject.to_string(), t.object.to_string())
+        };
+        assert_eq!(
+            read("tests/resources/relative.ttl"),
+            (
+                "<http://example.org/base/thing>".to_string(),
+                "<http://example.org/base/other#frag>".to_string()
+            )
+        );
+        // @base in the document takes precedence
+        assert_eq!(
+            read("tests/resources/relative_with_base.ttl"),
+            (
+                "<http://example.com/doc/thing>".to_string(),
+                "<http://example.com/doc/other#frag>".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_empty_prefix() {
+        let opts = Options::default();
+        let mut reader =
+            FileReader::new(vec!["tests/resources/empty_prefix.ttl".to_string()], &opts);
+        let triples: Vec<_> = std::iter::from_fn(|| reader.next_triple())
+            .map(|t| t.expect("error parsing triple").to_string())
+            .collect();
+        assert_eq!(
+            triples,
+            [
+                "<http://ex/Foo> <http://ex/> <http://example.com/doc/relative>",
+                "<http://example.com/doc/thing> <http://ex/p> <http://ex/Bar>",
+                "<http://example.com/doc/thing> <http://ex/p> <http://example.com/doc/ns/baz>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let opts = Options::default();
+        let mut reader = FileReader::new(vec!["tests/resources/latin1.ttl".to_string()], &opts);
+        let err = std::iter::from_fn(|| reader.next_triple())
+            .find_map(Result::err)
+            .expect("invalid UTF-8 should be reported");
+        match err {
+            Rdf2HdtError::Parse { line, column, msg } => {
+                assert_eq!((line, column), (4, 21));
+                assert!(msg.contains("at byte 87"), "{msg}");
+                assert!(msg.contains("latin1.ttl"), "{msg}");
+            }
+            e => panic!("unexpected error {e}"),
+        }
+
+        let opts = Options {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut reader = FileReader::new(vec!["tests/resources/latin1.ttl".to_string()], &opts);
+        let triples: Vec<_> = std::iter::from_fn(|| reader.next_triple())
+            .map(|t| t.expect("error parsing triple").to_string())
+            .collect();
+        assert_eq!(triples.len(), 2);
+        assert!(triples[1].contains("\"Caf\u{FFFD}\""), "{}", triples[1]);
+    }
+
+    #[test]
+    fn test_undeclared_prefix() -> Result<(), Rdf2HdtError> {
+        let files = vec!["tests/resources/undeclared_prefix.ttl".to_string()];
+        let opts = Options::default();
+        let mut reader = FileReader::new(files.clone(), &opts);
+        let err = std::iter::from_fn(|| reader.next_triple())
+            .find_map(Result::err)
+            .expect("undeclared prefix should be rejected");
+        match err {
+            Rdf2HdtError::Parse { line, column, msg } => {
+                assert_eq!((line, column), (4, 1));
+                assert!(msg.contains("undeclared:"), "{msg}");
+            }
+            e => panic!("expected a parse error, got {e:?}"),
+        }
+
+        let opts = Options {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut reader = FileReader::new(files, &opts);
+        let triples = std::iter::from_fn(|| reader.next_triple()).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(triples.len(), 2);
+        assert!(reader.stats.skipped > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_collections() -> Result<(), Rdf2HdtError> {
+        let opts = Options::default();
+        let mut reader =
+            FileReader::new(vec!["tests/resources/collections.ttl".to_string()], &opts);
+        let triples = std::iter::from_fn(|| reader.next_triple()).collect::<Result<Vec<_>, _>>()?;
+        let count = |predicate| triples.iter().filter(|t| t.predicate == predicate).count();
+        // `a`, two list heads, two lists of 3 and 2 items, and the empty list
+        assert_eq!(triples.len(), 13);
+        assert_eq!(count(rdf::TYPE), 1);
+        assert_eq!(count(rdf::FIRST), 5);
+        assert_eq!(count(rdf::REST), 5);
+        let nil = triples
+            .iter()
+            .filter(|t| t.object == Term::from(rdf::NIL))
+            .count();
+        assert_eq!(nil, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rdf_star_reify() {
+        let opts = Options {
+            rdf_star: RdfStarMode::Reify,
+            canonical_bnodes: true,
+            ..Default::default()
+        };
+        let read = || {
+            let mut reader =
+                FileReader::new(vec!["tests/resources/rdf_star.ttl".to_string()], &opts);
+            let mut triples = Vec::new();
+            while let Some(t) = reader.next_triple() {
+                triples.push(t.expect("error parsing triple"));
+            }
+            triples
+        };
+        let statements = |triples: Vec<Triple>| {
+            assert!(triples.iter().all(|t| !matches!(t.object, Term::Triple(_))));
+            triples
+                .into_iter()
+                .filter(|t| t.object == Term::from(rdf::STATEMENT))
+                .map(|t| t.subject)
+                .collect::<std::collections::HashSet<_>>()
+        };
+        let reified = statements(read());
+        // the nested quoted triple is reified as well, and the repeated one only once
+        assert_eq!(reified.len(), 2);
+        assert_eq!(reified, statements(read()));
+    }
+
+    #[test]
+    fn test_rdf_star_reject() {
+        let opts = Options::default();
+        let mut reader = FileReader::new(vec!["tests/resources/rdf_star.ttl".to_string()], &opts);
+        let err = std::iter::from_fn(|| reader.next_triple())
+            .find_map(Result::err)
+            .expect("quoted triple should be rejected");
+        assert!(matches!(err, Rdf2HdtError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_generalized_rdf() -> Result<(), Rdf2HdtError> {
+        let files = vec!["tests/resources/generalized.n3".to_string()];
+        let opts = Options::default();
+        let mut reader = FileReader::new(files.clone(), &opts);
+        let err = std::iter::from_fn(|| reader.next_triple())
+            .find_map(Result::err)
+            .expect("generalized triple should be rejected");
+        assert!(matches!(err, Rdf2HdtError::InvalidInput(_)));
+
+        let opts = Options {
+            generalized_rdf: GeneralizedRdfMode::Skip,
+            ..Default::default()
+        };
+        let mut reader = FileReader::new(files, &opts);
+        let triples = std::iter::from_fn(|| reader.next_triple()).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(triples.len(), 2);
+        assert_eq!(reader.stats.skipped, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nul_literal() -> Result<(), Rdf2HdtError> {
+        let files = vec!["tests/resources/nul_literal.ttl".to_string()];
+        let opts = Options::default();
+        let mut reader = FileReader::new(files.clone(), &opts);
+        let err = reader
+            .next_triple()
+            .expect("a triple or an error")
+            .expect_err("a literal with NUL should be rejected");
+        assert!(matches!(err, Rdf2HdtError::InvalidInput(_)));
+
+        let opts = Options {
+            lenient: true,
+            rdf_star: RdfStarMode::Reify,
+            ..Default::default()
+        };
+        let mut reader = FileReader::new(files, &opts);
+        let triples = std::iter::from_fn(|| reader.next_triple()).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(triples.len(), 1);
+        assert_eq!(reader.stats.skipped, 2);
+        Ok(())
+    }
+
+    fn gzip_bytes(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).expect("error compressing data");
+        encoder.finish().expect("error compressing data")
+    }
+
+    #[test]
+    fn test_gzip_multi_member() {
+        let ttl = std::fs::read("tests/resources/apple.ttl").expect("error reading apple.ttl");
+        let mut gz = gzip_bytes(&ttl);
+        gz.extend(gzip_bytes(&ttl));
+        let mut tmp_gz = tempfile::Builder::new()
+            .suffix(".ttl.gz")
+            .tempfile()
+            .expect("error creating tmp file");
+        tmp_gz.write_all(&gz).expect("error writing tmp file");
+
+        let tmp_file = tempfile::Builder::new().suffix(".nt").tempfile().expect("");
+        assert!(
+            convert_to_nt(
+                vec![tmp_gz.path().display().to_string()],
+                tmp_file.reopen().expect("error opening tmp file"),
+                &Options::default()
+            )
+            .is_ok()
+        );
+        let source_reader = BufReader::new(tmp_file.reopen().expect("error opening tmp file"));
+        let quads = RdfParser::from_format(NTriples)
+            .for_reader(source_reader)
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(quads.expect("invalid NT output").len(), 18)
+    }
+
+    #[test]
+    fn test_gzip_truncated() {
+        let ttl = std::fs::read("tests/resources/apple.ttl").expect("error reading apple.ttl");
+        let gz = gzip_bytes(&ttl);
+        let mut tmp_gz = tempfile::Builder::new()
+            .suffix(".ttl.gz")
+            .tempfile()
+            .expect("error creating tmp file");
+        tmp_gz
+            .write_all(&gz[..gz.len() / 2])
+            .expect("error writing tmp file");
+
+        let tmp_file = tempfile::Builder::new().suffix(".nt").tempfile().expect("");
+        assert!(
+            convert_to_nt(
+                vec![tmp_gz.path().display().to_string()],
+                tmp_file.reopen().expect("error opening tmp file"),
+                &Options::default()
+            )
+            .is_err()
+        );
+    }
 }
@@ -0,0 +1,242 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+use log::warn;
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// UTF-8 encoding of U+FEFF, which some editors write at the start of a file.
+pub(crate) const UTF8_BOM: &[u8; 3] = b"\xef\xbb\xbf";
+
+/// Position of an invalid UTF-8 sequence, returned inside an [`io::Error`] of
+/// kind [`io::ErrorKind::InvalidData`].
+#[derive(Debug)]
+pub(crate) struct InvalidUtf8 {
+    /// Offset of the first invalid byte.
+    pub(crate) offset: u64,
+    /// 1-based line of the first invalid byte.
+    pub(crate) line: usize,
+    /// 1-based column of the first invalid byte, counted in bytes.
+    pub(crate) column: usize,
+}
+
+impl InvalidUtf8 {
+    /// Returns the invalid sequence reported by `e`, if `e` comes from a [`Utf8Reader`].
+    pub(crate) fn from_io_error(e: &io::Error) -> Option<&Self> {
+        e.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for InvalidUtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid UTF-8 at byte {}", self.offset)
+    }
+}
+
+impl std::error::Error for InvalidUtf8 {}
+
+/// Passes bytes through once they are known to be valid UTF-8, without the byte
+/// order mark the input may start with.
+///
+/// An invalid sequence fails the read with an [`InvalidUtf8`] error, or when
+/// `lenient` is replaced with U+FFFD and logged.
+pub(crate) struct Utf8Reader<R> {
+    inner: R,
+    name: String,
+    lenient: bool,
+    /// Validated bytes not read yet, from `pos` on.
+    buf: Vec<u8>,
+    pos: usize,
+    /// Start of a multi-byte sequence cut by the end of the last chunk.
+    carry: Vec<u8>,
+    /// Input bytes validated so far.
+    offset: u64,
+    /// Line breaks validated so far.
+    lines: usize,
+    /// Offset of the first byte after the last line break.
+    line_start: u64,
+    /// Whether the start of the input was checked for a byte order mark.
+    bom_checked: bool,
+}
+
+impl<R: Read> Utf8Reader<R> {
+    /// Wraps `inner`, logging replacements under `name` when `lenient`.
+    pub(crate) fn new(inner: R, name: String, lenient: bool) -> Self {
+        Self {
+            inner,
+            name,
+            lenient,
+            buf: Vec::new(),
+            pos: 0,
+            carry: Vec::new(),
+            offset: 0,
+            lines: 0,
+            line_start: 0,
+            bom_checked: false,
+        }
+    }
+
+    /// Validates the next chunk of input into `buf`, returns `false` at the end of the input.
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = std::mem::take(&mut self.carry);
+        let start = chunk.len();
+        chunk.resize(start + CHUNK_SIZE, 0);
+        let read = self.inner.read(&mut chunk[start..])?;
+        chunk.truncate(start + read);
+        if chunk.is_empty() {
+            return Ok(false);
+        }
+        let at_end = read == 0;
+        if !self.bom_checked {
+            if chunk.len() < UTF8_BOM.len() && !at_end && UTF8_BOM.starts_with(&chunk) {
+                self.carry = chunk;
+                return Ok(true);
+            }
+            self.bom_checked = true;
+            if chunk.starts_with(UTF8_BOM) {
+                chunk.drain(..UTF8_BOM.len());
+                self.offset += UTF8_BOM.len() as u64;
+                self.line_start = self.offset;
+            }
+        }
+
+        self.buf.clear();
+        self.pos = 0;
+        let mut rest = &chunk[..];
+        loop {
+            let e = match std::str::from_utf8(rest) {
+                Ok(_) => {
+                    self.push_valid(rest);
+                    return Ok(true);
+                }
+                Err(e) => e,
+            };
+            let (valid, invalid) = rest.split_at(e.valid_up_to());
+            self.push_valid(valid);
+            let len = match e.error_len() {
+                // the sequence may be completed by the next chunk
+                None if !at_end => {
+                    self.carry = invalid.to_vec();
+                    return Ok(true);
+                }
+                None => invalid.len(),
+                Some(len) => len,
+            };
+            if !self.lenient {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    InvalidUtf8 {
+                        offset: self.offset,
+                        line: self.lines + 1,
+                        column: (self.offset - self.line_start) as usize + 1,
+                    },
+                ));
+            }
+            warn!(
+                "replacing invalid UTF-8 in {} at byte {} with U+FFFD",
+                self.name, self.offset
+            );
+            self.buf
+                .extend_from_slice(char::REPLACEMENT_CHARACTER.to_string().as_bytes());
+            self.offset += len as u64;
+            rest = &invalid[len..];
+        }
+    }
+
+    fn push_valid(&mut self, valid: &[u8]) {
+        self.buf.extend_from_slice(valid);
+        if let Some(last) = valid.iter().rposition(|b| *b == b'\n') {
+            self.line_start = self.offset + last as u64 + 1;
+        }
+        self.offset += valid.len() as u64;
+        self.lines += valid.iter().filter(|b| **b == b'\n').count();
+    }
+}
+
+impl<R: Read> Read for Utf8Reader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if !self.fill()? {
+                return Ok(0);
+            }
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns one byte per read, so that every multi-byte sequence is cut.
+    struct ByteByByte<'a>(&'a [u8]);
+
+    impl Read for ByteByByte<'_> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            out[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    fn read_all(input: &[u8], lenient: bool) -> io::Result<String> {
+        let mut out = String::new();
+        Utf8Reader::new(ByteByByte(input), "test".to_string(), lenient).read_to_string(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn valid_input() -> io::Result<()> {
+        let input = "\"μApple\" \"🍎\"\n";
+        assert_eq!(read_all(input.as_bytes(), false)?, input);
+        Ok(())
+    }
+
+    #[test]
+    fn byte_order_mark() -> io::Result<()> {
+        assert_eq!(
+            read_all(b"\xef\xbb\xbf<s> <p> \"o\" .\r\n", false)?,
+            "<s> <p> \"o\" .\r\n"
+        );
+        // only a leading byte order mark is removed
+        assert_eq!(read_all("\"\u{FEFF}\"".as_bytes(), false)?, "\"\u{FEFF}\"");
+        let err = read_all(b"\xef\xbb\xbf\xe9", false).unwrap_err();
+        let invalid = InvalidUtf8::from_io_error(&err).unwrap();
+        assert_eq!((invalid.offset, invalid.column), (3, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_input() {
+        let err = read_all(b"line one\n\"caf\xe9\"\n", false).unwrap_err();
+        let invalid = InvalidUtf8::from_io_error(&err).unwrap();
+        assert_eq!(invalid.offset, 13);
+        assert_eq!(invalid.line, 2);
+        assert_eq!(invalid.column, 5);
+    }
+
+    #[test]
+    fn truncated_sequence() {
+        let err = read_all(b"\"\xf0\x9f\x8d", false).unwrap_err();
+        assert_eq!(InvalidUtf8::from_io_error(&err).unwrap().offset, 1);
+    }
+
+    #[test]
+    fn lenient_replacement() -> io::Result<()> {
+        assert_eq!(
+            read_all(b"\"caf\xe9\" \"\xff\xfe\"", true)?,
+            "\"caf\u{FFFD}\" \"\u{FFFD}\u{FFFD}\""
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+/// IRIs of the RDF and XSD datatypes defined for RDF literals.
+///
+/// Typed literals are stored in the HDT dictionary as `"value"^^<datatype>` and
+/// sorted by that string, as the HDT format requires, so literals of the same
+/// datatype are only next to each other, and share a compressed prefix, when
+/// their values are. Literals of another datatype in the RDF or XSD namespace,
+/// usually misspelled, are reported in
+/// [`BuildStats::warnings`](crate::builder::BuildStats::warnings).
+pub const KNOWN_DATATYPES: &[&str] = &[
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#dirLangString",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#HTML",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#XMLLiteral",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON",
+    "http://www.w3.org/2001/XMLSchema#string",
+    "http://www.w3.org/2001/XMLSchema#boolean",
+    "http://www.w3.org/2001/XMLSchema#decimal",
+    "http://www.w3.org/2001/XMLSchema#integer",
+    "http://www.w3.org/2001/XMLSchema#double",
+    "http://www.w3.org/2001/XMLSchema#float",
+    "http://www.w3.org/2001/XMLSchema#date",
+    "http://www.w3.org/2001/XMLSchema#time",
+    "http://www.w3.org/2001/XMLSchema#dateTime",
+    "http://www.w3.org/2001/XMLSchema#dateTimeStamp",
+    "http://www.w3.org/2001/XMLSchema#gYear",
+    "http://www.w3.org/2001/XMLSchema#gMonth",
+    "http://www.w3.org/2001/XMLSchema#gDay",
+    "http://www.w3.org/2001/XMLSchema#gYearMonth",
+    "http://www.w3.org/2001/XMLSchema#gMonthDay",
+    "http://www.w3.org/2001/XMLSchema#duration",
+    "http://www.w3.org/2001/XMLSchema#yearMonthDuration",
+    "http://www.w3.org/2001/XMLSchema#dayTimeDuration",
+    "http://www.w3.org/2001/XMLSchema#byte",
+    "http://www.w3.org/2001/XMLSchema#short",
+    "http://www.w3.org/2001/XMLSchema#int",
+    "http://www.w3.org/2001/XMLSchema#long",
+    "http://www.w3.org/2001/XMLSchema#unsignedByte",
+    "http://www.w3.org/2001/XMLSchema#unsignedShort",
+    "http://www.w3.org/2001/XMLSchema#unsignedInt",
+    "http://www.w3.org/2001/XMLSchema#unsignedLong",
+    "http://www.w3.org/2001/XMLSchema#positiveInteger",
+    "http://www.w3.org/2001/XMLSchema#nonNegativeInteger",
+    "http://www.w3.org/2001/XMLSchema#negativeInteger",
+    "http://www.w3.org/2001/XMLSchema#nonPositiveInteger",
+    "http://www.w3.org/2001/XMLSchema#hexBinary",
+    "http://www.w3.org/2001/XMLSchema#base64Binary",
+    "http://www.w3.org/2001/XMLSchema#anyURI",
+    "http://www.w3.org/2001/XMLSchema#language",
+    "http://www.w3.org/2001/XMLSchema#normalizedString",
+    "http://www.w3.org/2001/XMLSchema#token",
+    "http://www.w3.org/2001/XMLSchema#NMTOKEN",
+    "http://www.w3.org/2001/XMLSchema#Name",
+    "http://www.w3.org/2001/XMLSchema#NCName",
+];
+
+/// Whether `iri` is one of the [`KNOWN_DATATYPES`].
+pub fn is_known_datatype(iri: &str) -> bool {
+    KNOWN_DATATYPES.contains(&iri)
+}
+
+/// Whether `iri` is in the RDF or XSD namespace without being one of the
+/// [`KNOWN_DATATYPES`], like the misspelled `xsd:integr`.
+pub(crate) fn is_unknown_datatype(iri: &str) -> bool {
+    (iri.starts_with("http://www.w3.org/1999/02/22-rdf-syntax-ns#")
+        || iri.starts_with("http://www.w3.org/2001/XMLSchema#"))
+        && !is_known_datatype(iri)
+}
+
+/// Prefixes of common vocabularies, used by [`compact_iri`].
+pub const COMMON_PREFIXES: &[(&str, &str)] = &[
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+];
+
+/// Shortens `iri` to a prefixed name such as `foaf:name` using
+/// [`COMMON_PREFIXES`], or returns it unchanged.
+///
+/// Meant for messages, see [`compact_iri_with`].
+pub fn compact_iri(iri: &str) -> String {
+    compact_iri_with(iri, &[])
+}
+
+/// Same as [`compact_iri`], also trying the `(prefix, namespace)` pairs of
+/// `extra`, such as [`Options::log_prefixes`](crate::builder::Options::log_prefixes).
+///
+/// The longest matching namespace wins, and `extra` wins over the common
+/// prefixes for the same namespace. IRIs whose remainder after the namespace
+/// contains `/`, `#` or `?` are left unchanged, since the prefixed name would
+/// hide part of their structure.
+pub fn compact_iri_with(iri: &str, extra: &[(String, String)]) -> String {
+    let extra = extra.iter().map(|(p, ns)| (p.as_str(), ns.as_str()));
+    let common = COMMON_PREFIXES.iter().copied();
+    let mut best: Option<(&str, &str)> = None;
+    for (prefix, namespace) in extra.chain(common) {
+        let Some(local) = iri.strip_prefix(namespace) else {
+            continue;
+        };
+        if local.contains(['/', '#', '?']) {
+            continue;
+        }
+        if best.is_none_or(|(_, best_local)| local.len() < best_local.len()) {
+            best = Some((prefix, local));
+        }
+    }
+    match best {
+        Some((prefix, local)) => format!("{prefix}:{local}"),
+        None => iri.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxrdf::vocab::{rdf, xsd};
+
+    #[test]
+    fn compact_iris() {
+        assert_eq!(compact_iri(rdf::TYPE.as_str()), "rdf:type");
+        assert_eq!(compact_iri("http://xmlns.com/foaf/0.1/name"), "foaf:name");
+        assert_eq!(
+            compact_iri("http://example.org/people/alice"),
+            "http://example.org/people/alice"
+        );
+        // the remainder would hide a path
+        assert_eq!(
+            compact_iri("http://xmlns.com/foaf/0.1/a/b"),
+            "http://xmlns.com/foaf/0.1/a/b"
+        );
+        let extra = [
+            ("ex".to_string(), "http://example.org/".to_string()),
+            (
+                "people".to_string(),
+                "http://example.org/people/".to_string(),
+            ),
+        ];
+        assert_eq!(
+            compact_iri_with("http://example.org/people/alice", &extra),
+            "people:alice"
+        );
+        assert_eq!(compact_iri_with("http://example.org/a", &extra), "ex:a");
+    }
+
+    #[test]
+    fn known_datatypes() {
+        for datatype in [xsd::INTEGER, xsd::STRING, rdf::LANG_STRING] {
+            assert!(is_known_datatype(datatype.as_str()), "{datatype}");
+        }
+        assert!(!is_known_datatype("http://www.w3.org/2001/XMLSchema#QName"));
+        assert!(!is_known_datatype("http://example.org/integer"));
+        let unique = KNOWN_DATATYPES
+            .iter()
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(unique.len(), KNOWN_DATATYPES.len());
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2025, Decisym, LLC
+// Licensed under the BSD 3-Clause License (see LICENSE file in the project root).
+
+//! Builds HDTs from random triple sets and checks that reading them back with
+//! the hdt crate gives the same triples.
+
+use oxrdfio::RdfFormat;
+use proptest::prelude::*;
+use rdf2hdt::builder::{Options, build_hdt_from_str};
+use std::collections::BTreeSet;
+
+/// A term as N-Triples, and as the hdt crate stores it in the dictionary.
+#[derive(Debug, Clone)]
+struct TestTerm {
+    nt: String,
+    hdt: String,
+}
+
+/// IRIs with multibyte characters, so dictionary prefixes can end inside one.
+fn iri() -> impl Strategy<Value = TestTerm> {
+    "[a-zA-Z0-9._~/é€😀-]{0,12}".prop_map(|path| {
+        let iri = format!("http://example.org/{path}");
+        TestTerm {
+            nt: format!("<{iri}>"),
+            hdt: iri,
+        }
+    })
+}
+
+fn bnode() -> impl Strategy<Value = TestTerm> {
+    "[a-zA-Z][a-zA-Z0-9]{0,6}".prop_map(|label| TestTerm {
+        nt: format!("_:{label}"),
+        hdt: format!("_:{label}"),
+    })
+}
+
+/// Literal values with quotes, backslashes, line breaks and any other character
+/// but U+0000, which rdf2hdt rejects.
+fn value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<String>(),
+        "[\"\\\\\n\r\t a\u{e9}\u{20ac}\u{1f600}]{0,8}",
+    ]
+    .prop_map(|value| value.replace('\0', ""))
+}
+
+fn literal() -> impl Strategy<Value = TestTerm> {
+    let suffix = prop_oneof![
+        Just(None),
+        "[a-z]{2,3}(-[a-z]{2})?".prop_map(|lang| Some(format!("@{lang}"))),
+        "[a-z]{1,6}".prop_map(|dt| Some(format!("^^<http://example.org/dt/{dt}>"))),
+    ];
+    (value(), suffix).prop_map(|(value, suffix)| {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        let suffix = suffix.unwrap_or_default();
+        TestTerm {
+            nt: format!("\"{escaped}\"{suffix}"),
+            hdt: format!("\"{value}\"{suffix}"),
+        }
+    })
+}
+
+fn triple() -> impl Strategy<Value = [TestTerm; 3]> {
+    (
+        prop_oneof![iri(), bnode()],
+        iri(),
+        prop_oneof![iri(), bnode(), literal()],
+    )
+        .prop_map(|(s, p, o)| [s, p, o])
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn roundtrip(triples in prop::collection::vec(triple(), 1..40)) {
+        let nt = triples
+            .iter()
+            .map(|[s, p, o]| format!("{} {} {} .\n", s.nt, p.nt, o.nt))
+            .collect::<String>();
+        let expected = triples
+            .iter()
+            .map(|t| t.clone().map(|term| term.hdt))
+            .collect::<BTreeSet<_>>();
+
+        let opts = Options {
+            // keep the blank node labels as written
+            shared_bnode_scope: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        build_hdt_from_str(&nt, RdfFormat::NTriples, &mut out, &opts)
+            .map_err(|e| TestCaseError::fail(format!("{e}\n{nt}")))?;
+        let hdt = hdt::Hdt::read(std::io::Cursor::new(out))
+            .map_err(|e| TestCaseError::fail(format!("{e}\n{nt}")))?;
+        let actual = hdt
+            .triples_with_pattern(None, None, None)
+            .map(|t| t.map(|term| term.to_string()))
+            .collect::<BTreeSet<_>>();
+        prop_assert_eq!(actual, expected, "{}", nt);
+    }
+}